@@ -0,0 +1,20 @@
+//! Feeds arbitrary bytes through every parser in `file_reader`. None of them may panic, whatever
+//! the input; malformed files must come back as a `FileReadError`.
+//!
+//! Run with `cargo fuzz run parse_game`. The files in `corpus/parse_game` are truncated and
+//! corrupted Blorb, Glulx and Z-code files that used to panic, and double as regression tests:
+//! `cargo fuzz run parse_game corpus/parse_game -- -runs=0` replays them once each, and
+//! `cargo test` replays them through the same calls, which live in `file_reader::fuzz_exercise`.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// The browser is a binary crate, so the parsers are compiled straight into the fuzz target.
+#[allow(dead_code)]
+#[path = "../../src/file_reader/mod.rs"]
+mod file_reader;
+#[allow(dead_code)]
+#[path = "../../src/strings.rs"]
+mod strings;
+
+fuzz_target!(|data: &[u8]| file_reader::fuzz_exercise::exercise(data));
@@ -0,0 +1,179 @@
+use std::fmt::{Display, Formatter};
+
+use super::blorb_chunk_types::BlorbChunkType;
+use super::FileReadErrorKind::{InvalidLength, InvalidSampleSize, MissingChunk};
+use super::{read_be_u16, read_be_u32, read_bytes, FileReadError};
+
+/// An AIFF sound, read from the data of a `FORM` chunk whose form type is `AIFF`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AiffSound<'a> {
+    pub common: CommonChunk,
+    /// Only optional when there are no sample frames.
+    pub sound_data: Option<SoundDataChunk<'a>>,
+}
+
+/// The `COMM` chunk, describing the format of the samples.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CommonChunk {
+    pub channels: u16,
+    pub sample_frames: u32,
+    /// Bits per sample, from 1 to 32.
+    pub sample_size: u16,
+    /// Sample frames per second.
+    pub sample_rate: f64,
+}
+
+/// The `SSND` chunk, holding the interleaved sample frames.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct SoundDataChunk<'a> {
+    /// Where the first sample frame starts in `samples`.
+    pub offset: u32,
+    pub block_size: u32,
+    pub samples: &'a [u8],
+}
+
+/// The size of the form type at the start of the FORM's data.
+const FORM_TYPE_SIZE: usize = 4;
+
+impl<'a> TryFrom<&'a [u8]> for AiffSound<'a> {
+    type Error = FileReadError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let mut common = None;
+        let mut sound_data = None;
+        let mut sound_data_offset = 0;
+        let mut offset = FORM_TYPE_SIZE;
+        while offset < value.len() {
+            let chunk_id = read_be_u32(value, offset)?;
+            let len = read_be_u32(value, offset + 4)? as usize;
+            let data = read_bytes(value, offset + 8, len)?;
+            let parsed = match BlorbChunkType::try_from(chunk_id) {
+                Ok(BlorbChunkType::AIFF_COMMON) => CommonChunk::try_from(data).map(|c| {
+                    common = Some(c);
+                }),
+                Ok(BlorbChunkType::AIFF_SOUND_DATA) => SoundDataChunk::try_from(data).map(|s| {
+                    sound_data = Some(s);
+                    sound_data_offset = offset;
+                }),
+                _ => Ok(()),
+            };
+            parsed.map_err(|e| e.offset_by(offset + 8))?;
+            offset += 8 + len + (len & 1);
+        }
+
+        let common = common.ok_or(FileReadError::new(MissingChunk(
+            BlorbChunkType::AIFF_COMMON,
+        )))?;
+        if common.sample_frames > 0 {
+            let sound_data = sound_data.ok_or(FileReadError::new(MissingChunk(
+                BlorbChunkType::AIFF_SOUND_DATA,
+            )))?;
+            let expected = common.data_len();
+            let actual = sound_data.frames().len() as u64;
+            if actual < expected {
+                return Err(
+                    FileReadError::new(InvalidLength(actual as usize, expected as usize))
+                        .at(sound_data_offset)
+                        .in_chunk(BlorbChunkType::AIFF_SOUND_DATA),
+                );
+            }
+        }
+        Ok(AiffSound { common, sound_data })
+    }
+}
+
+impl TryFrom<&[u8]> for CommonChunk {
+    type Error = FileReadError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let rate: [u8; 10] = read_bytes(value, 8, 10)?.try_into().unwrap();
+        let sample_size = read_be_u16(value, 6)?;
+        if !(1..=32).contains(&sample_size) {
+            return Err(FileReadError::new(InvalidSampleSize(sample_size)).at(6));
+        }
+        Ok(CommonChunk {
+            channels: read_be_u16(value, 0)?,
+            sample_frames: read_be_u32(value, 2)?,
+            sample_size,
+            sample_rate: extended_to_f64(rate),
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for SoundDataChunk<'a> {
+    type Error = FileReadError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(SoundDataChunk {
+            offset: read_be_u32(value, 0)?,
+            block_size: read_be_u32(value, 4)?,
+            samples: &value[8..],
+        })
+    }
+}
+
+impl CommonChunk {
+    /// Bytes per sample: samples are padded out to whole bytes.
+    pub fn bytes_per_sample(&self) -> usize {
+        (self.sample_size as usize).div_ceil(8)
+    }
+
+    /// How many bytes of sample data the sound should have.
+    pub fn data_len(&self) -> u64 {
+        self.sample_frames as u64 * self.channels as u64 * self.bytes_per_sample() as u64
+    }
+
+    /// The length of the sound in seconds, or zero if the sample rate makes no sense.
+    pub fn duration(&self) -> f64 {
+        if self.sample_rate > 0.0 {
+            self.sample_frames as f64 / self.sample_rate
+        } else {
+            0.0
+        }
+    }
+}
+
+impl<'a> SoundDataChunk<'a> {
+    /// The sample frames, skipping the alignment padding that `offset` describes.
+    pub fn frames(&self) -> &'a [u8] {
+        self.samples.get(self.offset as usize..).unwrap_or_default()
+    }
+}
+
+/// Converts the 80-bit IEEE 754 extended precision float AIFF stores the sample rate in. Its 64
+/// bit mantissa has an explicit integer bit, unlike f64's.
+pub fn extended_to_f64(bytes: [u8; 10]) -> f64 {
+    let sign = if bytes[0] & 0x80 != 0 { -1.0 } else { 1.0 };
+    let exponent = (u16::from_be_bytes([bytes[0], bytes[1]]) & 0x7FFF) as i32;
+    let mantissa = u64::from_be_bytes(bytes[2..].try_into().unwrap());
+    if exponent == 0x7FFF {
+        return if mantissa << 1 == 0 {
+            sign * f64::INFINITY
+        } else {
+            f64::NAN
+        };
+    }
+    if mantissa == 0 {
+        return 0.0;
+    }
+    sign * mantissa as f64 * 2f64.powi(exponent - 16383 - 63)
+}
+
+impl Display for AiffSound<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Format: AIFF")?;
+        writeln!(f, "Channels: {}", self.common.channels)?;
+        writeln!(f, "Sample rate: {} Hz", self.common.sample_rate)?;
+        writeln!(
+            f,
+            "Sample frames: {} ({:.2} seconds)",
+            self.common.sample_frames,
+            self.common.duration()
+        )?;
+        writeln!(f, "Bit depth: {}", self.common.sample_size)?;
+        if let Some(sound_data) = &self.sound_data {
+            writeln!(f, "Sound data: {} bytes", sound_data.frames().len())?;
+        }
+        Ok(())
+    }
+}
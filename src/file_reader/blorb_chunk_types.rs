@@ -1,7 +1,7 @@
 use std::convert::TryFrom;
 use std::hash::Hash;
 
-use crate::file_reader::FileReadError;
+use crate::file_reader::{FileReadError, FileReadErrorKind};
 use BlorbChunkType::*;
 
 #[allow(clippy::upper_case_acronyms)]
@@ -27,7 +27,12 @@ pub enum BlorbChunkType {
     //PNG
     PICTURE_JPEG = 0x4a504547,
     //JPEG
-    //	AIFF, //The chunk is a FORM type with an AIFF chunk inside
+    PICTURE_RECT = 0x52656374,
+    //Rect (a placeholder with a size but no image)
+    SOUND_AIFF = 0x41494646,
+    //AIFF (the chunk is a FORM with AIFF as its form type)
+    SOUND_OGG = 0x4f474756,
+    //OGGV
     SOUND_MOD = 0x4d4f4420,
     //MOD
     SOUND_SONG = 0x534f4e47,
@@ -49,14 +54,40 @@ pub enum BlorbChunkType {
     //RelN
     IF_HEADER = 0x49466864,
     //IFhd
+    IFICTION_METADATA = 0x49466d64,
+    //IFmd
+    FRONTISPIECE = 0x46737063,
+    //Fspc
+    RESOURCE_DESCRIPTION = 0x52446573,
+    //RDes
+    STORY_NAME = 0x534e616d,
+    //SNam
     //Optional, in many IFF FORMs-------------------------------------------------------------------
     AUTHOR = 0x41555448,
     //AUTH
-    COPYRIGHT = 0x2863295f,
-    //(c)_
+    COPYRIGHT = 0x28632920,
+    //(c)_ (the last character is a space)
     ANNOTATION = 0x414e4e4f, //ANNO
 
     INFO = 0x496E666F,
+
+    //Inside AIFF FORMs-----------------------------------------------------------------------------
+    AIFF_COMMON = 0x434f4d4d,
+    //COMM
+    AIFF_SOUND_DATA = 0x53534e44,
+    //SSND
+
+    //Inside OGGV chunks----------------------------------------------------------------------------
+    OGG_PAGE = 0x4f676753,
+    //OggS
+    /// A chunk type this reader doesn't recognise. The chunk's own FourCC is kept in
+    /// `Chunk::fourcc`.
+    UNKNOWN = 0,
+}
+
+/// A FourCC as the four characters it spells, for showing chunk types we don't recognise.
+pub fn fourcc_to_string(fourcc: u32) -> String {
+    String::from_utf8_lossy(&fourcc.to_be_bytes()).into_owned()
 }
 
 impl TryFrom<u32> for BlorbChunkType {
@@ -65,6 +96,13 @@ impl TryFrom<u32> for BlorbChunkType {
     fn try_from(value: u32) -> Result<Self, Self::Error> {
         match value {
             0x49466864 => Ok(IF_HEADER),
+            0x49466d64 => Ok(IFICTION_METADATA),
+            0x46737063 => Ok(FRONTISPIECE),
+            0x52446573 => Ok(RESOURCE_DESCRIPTION),
+            0x534e616d => Ok(STORY_NAME),
+            0x41555448 => Ok(AUTHOR),
+            0x28632920 => Ok(COPYRIGHT),
+            0x414e4e4f => Ok(ANNOTATION),
             0x464f524d => Ok(FORM),
             0x49465253 => Ok(IFRS),
             0x52496478 => Ok(RESOURCE_INDEX),
@@ -78,12 +116,18 @@ impl TryFrom<u32> for BlorbChunkType {
             0x52656c4e => Ok(RELEASE_NUMBER),
             0x504E4720 => Ok(PICTURE_PNG),
             0x4a504547 => Ok(PICTURE_JPEG),
+            0x52656374 => Ok(PICTURE_RECT),
+            0x41494646 => Ok(SOUND_AIFF),
+            0x4f474756 => Ok(SOUND_OGG),
             0x4d4f4420 => Ok(SOUND_MOD),
             0x534f4e47 => Ok(SOUND_SONG),
             0x5a434f44 => Ok(EXEC_ZCOD),
             0x474c554c => Ok(EXEC_GLUL),
             0x496E666F => Ok(INFO),
-            _ => Err(FileReadError::UnknownIdentifier(value as usize)),
+            0x434f4d4d => Ok(AIFF_COMMON),
+            0x53534e44 => Ok(AIFF_SOUND_DATA),
+            0x4f676753 => Ok(OGG_PAGE),
+            _ => Err(FileReadErrorKind::UnknownIdentifier(value as usize).into()),
         }
     }
 }
@@ -1,22 +1,40 @@
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 
-use super::blorb_chunk_types::BlorbChunkType;
+use super::aiff::AiffSound;
+use super::blorb_chunk_types::{fourcc_to_string, BlorbChunkType};
+use super::ifiction::IFictionMetadata;
+use super::ogg::VorbisSound;
+use super::tracker::TrackerModule;
 use super::ulx_reader::UlxReader;
-use super::FileReadError::{InvalidLength, UnexpectedStartingIdentifier};
-use super::{read_be_u32, FileReadError};
+use super::zcode_reader::ZcodeReader;
+use super::FileReadErrorKind::{
+    InvalidChunk, InvalidColourDepth, InvalidConversion, InvalidLength, InvalidResource,
+    InvalidResourceIndex, InvalidSound, UnexpectedStartingIdentifier,
+};
+use super::{read_be_u32, read_bytes, FileReadError};
 
-struct FileIndex<'a>(HashMap<BlorbChunkType, HashMap<i32, Chunk<'a>>>);
+#[derive(Clone)]
+struct FileIndex<'a> {
+    resources: HashMap<BlorbChunkType, HashMap<i32, Chunk<'a>>>,
+    /// Index entries whose usage isn't Pict, Snd, Data or Exec, as (usage, id, chunk).
+    unknown_usage: Vec<(u32, i32, Chunk<'a>)>,
+}
 
+#[derive(Clone)]
 pub struct BlorbReader<'a> {
     file_index: FileIndex<'a>,
+    /// Every chunk directly inside the FORM, in file order, up to the first that can't be read.
+    chunks: Vec<Chunk<'a>>,
+    /// Why the chunks after the last in `chunks` couldn't be read.
+    chunk_error: Option<FileReadError>,
     // optional_fields_used: Vec<BlorbChunkType>,
 }
 
 impl Display for BlorbReader<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
         f.write_str("BlorbReader{ file_index: ")?;
-        for (k, v) in &self.file_index.0 {
+        for (k, v) in &self.file_index.resources {
             f.write_fmt(format_args!("{:?}{{", k))?;
             for (k2, v2) in v {
                 f.write_fmt(format_args!("ID: {}, {}", k2, v2))?;
@@ -28,8 +46,21 @@ impl Display for BlorbReader<'_> {
     }
 }
 
+/// A resource the reader kept without understanding it.
+pub struct OtherChunk<'a> {
+    /// The FourCC of the resource index usage, such as `Pict` or `Snd `.
+    pub usage: u32,
+    pub id: i32,
+    pub chunk: &'a Chunk<'a>,
+}
+
+#[derive(Clone)]
 pub struct Chunk<'a> {
+    /// `BlorbChunkType::UNKNOWN` if the reader doesn't recognise `fourcc`.
     pub chunk_type: BlorbChunkType,
+    pub fourcc: u32,
+    /// Where the chunk's header starts in the file.
+    pub offset: usize,
     pub data: &'a [u8],
 }
 
@@ -37,55 +68,454 @@ impl<'a> TryFrom<&'a [u8]> for Chunk<'a> {
     type Error = FileReadError;
 
     fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
-        if value.len() < 8 {
-            return Err(InvalidLength(value.len(), 8));
+        Chunk::at(value, 0)
+    }
+}
+
+impl<'a> Chunk<'a> {
+    /// Reads the chunk whose header starts at `offset` in `file`, so that errors report where in
+    /// the file they happened. A nested FORM whose form type is known, such as an AIFF sound,
+    /// takes that type.
+    pub fn at(file: &'a [u8], offset: usize) -> Result<Chunk<'a>, FileReadError> {
+        let fourcc = read_be_u32(file, offset)?;
+        let mut chunk_type = BlorbChunkType::try_from(fourcc).unwrap_or(BlorbChunkType::UNKNOWN);
+        let len = read_be_u32(file, offset + 4).map_err(|e| e.in_chunk(chunk_type))?;
+        let data =
+            read_bytes(file, offset + 8, len as usize).map_err(|e| e.in_chunk(chunk_type))?;
+        if chunk_type == BlorbChunkType::FORM
+            && read_be_u32(data, 0).ok() == Some(BlorbChunkType::SOUND_AIFF as u32)
+        {
+            chunk_type = BlorbChunkType::SOUND_AIFF;
+        }
+        Ok(Chunk {
+            chunk_type,
+            fourcc,
+            offset,
+            data,
+        })
+    }
+}
+
+impl Chunk<'_> {
+    /// The text of a chunk whose whole body is a string: `AUTH`, `(c) ` and `ANNO` hold ASCII and
+    /// `SNam` holds UTF-16.
+    pub fn text(&self) -> Option<String> {
+        match self.chunk_type {
+            BlorbChunkType::AUTHOR | BlorbChunkType::COPYRIGHT | BlorbChunkType::ANNOTATION => {
+                Some(String::from_utf8_lossy(self.data).into_owned())
+            }
+            BlorbChunkType::STORY_NAME => {
+                let units: Vec<u16> = self
+                    .data
+                    .chunks_exact(2)
+                    .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                    .collect();
+                Some(String::from_utf16_lossy(&units))
+            }
+            _ => None,
         }
-        let chunk_type = read_be_u32(&value[..4]).try_into()?;
-        let len = read_be_u32(&value[4..8]);
-        let data = &value[8..][..(len as usize)];
-        Ok(Chunk { chunk_type, data })
     }
 }
 
 impl<'a> Display for Chunk<'a> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
-        f.write_fmt(format_args!("Chunk type: {:?}", self.chunk_type,))?;
+        f.write_fmt(format_args!(
+            "Chunk type: {:?} ({})",
+            self.chunk_type,
+            fourcc_to_string(self.fourcc)
+        ))?;
         Ok(())
     }
 }
 
+/// Where the resource index chunk must start: straight after the FORM header (FORM, len, IFRS).
+const INDEX_OFFSET: usize = 12;
+
+/// Reads the resource index from a whole Blorb file, so that chunk addresses and error offsets
+/// are both relative to the start of the file.
 impl<'a> TryFrom<&'a [u8]> for FileIndex<'a> {
     type Error = FileReadError;
 
     fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
         const INDEX_HEADER_SIZE: usize = 12;
         const CHUNK_HEADER_SIZE: usize = 12;
-        if read_be_u32(&value[..4]) != BlorbChunkType::RESOURCE_INDEX as u32 {
-            return Err(UnexpectedStartingIdentifier(BlorbChunkType::RESOURCE_INDEX));
+        if read_be_u32(value, INDEX_OFFSET)? != BlorbChunkType::RESOURCE_INDEX as u32 {
+            return Err(FileReadError::new(UnexpectedStartingIdentifier(
+                BlorbChunkType::RESOURCE_INDEX,
+            ))
+            .at(INDEX_OFFSET));
         }
-        // let index_len = read_be_u32(&v[4..8]);
-        let num_in_index = read_be_u32(&value[8..12]);
+        // let index_len = read_be_u32(value, INDEX_OFFSET + 4)?;
+        let num_in_index = read_be_u32(value, INDEX_OFFSET + 8)?;
 
+        let mut unknown_usage = Vec::new();
         let mut ret = HashMap::new();
         ret.insert(BlorbChunkType::PICTURE, HashMap::new());
         ret.insert(BlorbChunkType::SOUND, HashMap::new());
         ret.insert(BlorbChunkType::DATA, HashMap::new());
         ret.insert(BlorbChunkType::EXECUTABLE, HashMap::new());
 
-        for i in 0..num_in_index {
-            let offset = INDEX_HEADER_SIZE + (i as usize * CHUNK_HEADER_SIZE);
-            let key = read_be_u32(&value[offset..(offset + 4)]).try_into()?;
-            let id = i32::from_be_bytes(value[(offset + 4)..(offset + 8)].try_into().unwrap());
-            let address = read_be_u32(&value[(offset + 8)..(offset + 12)]);
+        for i in 0..num_in_index as usize {
+            let offset = INDEX_OFFSET + INDEX_HEADER_SIZE + (i * CHUNK_HEADER_SIZE);
+            let usage = read_be_u32(value, offset)?;
+            let key = BlorbChunkType::try_from(usage).unwrap_or(BlorbChunkType::UNKNOWN);
+            let id = read_be_u32(value, offset + 4)? as i32;
+            let address = read_be_u32(value, offset + 8)?;
+            let chunk = Chunk::at(value, address as usize).map_err(|e| {
+                e.context(InvalidResource)
+                    .at(offset)
+                    .in_chunk(key)
+                    .for_resource(id)
+            })?;
+
+            if key == BlorbChunkType::UNKNOWN {
+                unknown_usage.push((usage, id, chunk));
+            } else {
+                ret.entry(key)
+                    .or_insert_with(HashMap::new)
+                    .insert(id, chunk);
+            }
+        }
+
+        Ok(FileIndex {
+            resources: ret,
+            unknown_usage,
+        })
+    }
+}
+
+/// The text descriptions the `RDes` chunk gives resources, so that pictures and sounds can be
+/// described to players who can't see or hear them.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct ResourceDescriptions<'a> {
+    pub descriptions: HashMap<BlorbChunkType, HashMap<i32, &'a str>>,
+}
+
+/// Reads the data of an `RDes` chunk: a count, then a usage, resource ID, length and UTF-8 text
+/// for each entry. Entries with a usage this reader doesn't know are skipped.
+impl<'a> TryFrom<&'a [u8]> for ResourceDescriptions<'a> {
+    type Error = FileReadError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let count = read_be_u32(value, 0)?;
+        let mut descriptions: HashMap<BlorbChunkType, HashMap<i32, &'a str>> = HashMap::new();
+        let mut offset = 4;
+        for _ in 0..count {
+            let usage = read_be_u32(value, offset)?;
+            let id = read_be_u32(value, offset + 4)? as i32;
+            let len = read_be_u32(value, offset + 8)? as usize;
+            let text = read_bytes(value, offset + 12, len)?;
+            let text = std::str::from_utf8(text).map_err(|e| {
+                FileReadError::new(InvalidConversion)
+                    .at(offset + 12 + e.valid_up_to())
+                    .for_resource(id)
+            })?;
+            if let Ok(usage) = BlorbChunkType::try_from(usage) {
+                descriptions.entry(usage).or_default().insert(id, text);
+            }
+            offset += 12 + len;
+        }
+        Ok(ResourceDescriptions { descriptions })
+    }
+}
+
+impl<'a> ResourceDescriptions<'a> {
+    pub fn get(&self, usage: BlorbChunkType, id: i32) -> Option<&'a str> {
+        self.descriptions.get(&usage)?.get(&id).copied()
+    }
+}
+
+/// How many times a sound plays each time the game starts it.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Repeats {
+    Forever,
+    Times(u32),
+}
+
+impl Default for Repeats {
+    fn default() -> Self {
+        Repeats::Times(1)
+    }
+}
+
+impl Display for Repeats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Repeats::Forever => write!(f, "loops forever"),
+            Repeats::Times(1) => write!(f, "plays once"),
+            Repeats::Times(n) => write!(f, "plays {n} times"),
+        }
+    }
+}
+
+/// The repeat counts the `Loop` chunk gives sounds. Sounds it doesn't list play once.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct Loop {
+    /// Sound resource IDs and their repeats, in file order.
+    pub entries: Vec<(i32, Repeats)>,
+}
+
+/// Reads the data of a `Loop` chunk: a sound resource ID and repeat count for each entry, where a
+/// count of 0 means the sound loops forever.
+impl TryFrom<&[u8]> for Loop {
+    type Error = FileReadError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if !value.len().is_multiple_of(8) {
+            return Err(FileReadError::new(InvalidLength(
+                value.len(),
+                value.len() - value.len() % 8,
+            ))
+            .at(value.len() - value.len() % 8));
+        }
+        let entries = (0..value.len())
+            .step_by(8)
+            .map(|offset| {
+                let id = read_be_u32(value, offset)? as i32;
+                let repeats = match read_be_u32(value, offset + 4)? {
+                    0 => Repeats::Forever,
+                    n => Repeats::Times(n),
+                };
+                Ok((id, repeats))
+            })
+            .collect::<Result<_, FileReadError>>()?;
+        Ok(Loop { entries })
+    }
+}
+
+impl Loop {
+    pub fn repeats(&self, id: i32) -> Repeats {
+        self.entries
+            .iter()
+            .find(|(entry_id, _)| *entry_id == id)
+            .map(|(_, repeats)| *repeats)
+            .unwrap_or_default()
+    }
+
+    /// The IDs of entries that don't name any of `sound_ids`.
+    pub fn unknown_ids(&self, sound_ids: &[i32]) -> Vec<i32> {
+        self.entries
+            .iter()
+            .map(|(id, _)| *id)
+            .filter(|id| !sound_ids.contains(id))
+            .collect()
+    }
+}
+
+/// Where a `SONG` sample's data comes from. Blorb requires each sample that's used to be named
+/// `SNDnnnn`, after the AIFF sound resource holding its data.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum SongSample {
+    /// The sample's name doesn't refer to a sound, so it's empty.
+    Unused,
+    Aiff(i32),
+    /// The named sound resource doesn't exist.
+    Missing(i32),
+    /// The named sound resource isn't an AIFF.
+    NotAiff(i32),
+}
 
-            // The address is from the start of the blorb, which is 12 bytes earlier than the
-            // start of the index (the value here) (FORM, len, IFRS).
-            ret.entry(key)
-                .or_insert_with(HashMap::new)
-                .insert(id, value[address as usize - 12..].try_into().unwrap());
+impl Display for SongSample {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SongSample::Unused => write!(f, "Unused"),
+            SongSample::Aiff(id) => write!(f, "Sound {id}"),
+            SongSample::Missing(id) => write!(f, "Sound {id} is missing"),
+            SongSample::NotAiff(id) => write!(f, "Sound {id} isn't an AIFF"),
         }
+    }
+}
+
+/// A `Rect` picture: blank space of a given size, which the game draws into itself.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Placeholder {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl TryFrom<&[u8]> for Placeholder {
+    type Error = FileReadError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        Ok(Placeholder {
+            width: read_be_u32(value, 0)?,
+            height: read_be_u32(value, 4)?,
+        })
+    }
+}
+
+impl Display for Placeholder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} × {}", self.width, self.height)
+    }
+}
+
+/// A fraction from the `Reso` chunk.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Ratio {
+    pub numerator: u32,
+    pub denominator: u32,
+}
+
+impl Ratio {
+    /// `None` if either part is zero, which for minimum and maximum ratios means no limit.
+    pub fn value(&self) -> Option<f64> {
+        (self.numerator != 0 && self.denominator != 0)
+            .then(|| self.numerator as f64 / self.denominator as f64)
+    }
+}
+
+impl Display for Ratio {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.numerator, self.denominator)
+    }
+}
+
+/// How one picture is scaled as the game window grows and shrinks.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct ImageScaling {
+    /// The scale the picture is drawn at in a window of the standard size.
+    pub ratio: Ratio,
+    pub min: Ratio,
+    pub max: Ratio,
+}
 
-        Ok(FileIndex(ret))
+impl ImageScaling {
+    /// The scale the picture is drawn at in a window `elbow_room` times the standard size.
+    pub fn scale(&self, elbow_room: f64) -> f64 {
+        let mut scale = elbow_room * self.ratio.value().unwrap_or(1.0);
+        if let Some(min) = self.min.value() {
+            scale = scale.max(min);
+        }
+        if let Some(max) = self.max.value() {
+            scale = scale.min(max);
+        }
+        scale
+    }
+}
+
+/// The window sizes from the `Reso` chunk and the scaling rules for each picture listed in it.
+/// Sizes are width then height, with zero meaning no limit for the minimum and maximum.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Resolution {
+    pub standard: (u32, u32),
+    pub min: (u32, u32),
+    pub max: (u32, u32),
+    pub images: HashMap<i32, ImageScaling>,
+}
+
+const RESOLUTION_HEADER_SIZE: usize = 24;
+const IMAGE_SCALING_SIZE: usize = 28;
+
+/// Reads the data of a `Reso` chunk: the standard, minimum and maximum window sizes, then a
+/// picture resource ID and standard, minimum and maximum ratios for each entry.
+impl TryFrom<&[u8]> for Resolution {
+    type Error = FileReadError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let entries_len = value.len().saturating_sub(RESOLUTION_HEADER_SIZE);
+        if !entries_len.is_multiple_of(IMAGE_SCALING_SIZE) {
+            let expected = value.len() - entries_len % IMAGE_SCALING_SIZE;
+            return Err(FileReadError::new(InvalidLength(value.len(), expected)).at(expected));
+        }
+        let size = |offset| -> Result<(u32, u32), FileReadError> {
+            Ok((read_be_u32(value, offset)?, read_be_u32(value, offset + 4)?))
+        };
+        let ratio = |offset| -> Result<Ratio, FileReadError> {
+            let (numerator, denominator) = size(offset)?;
+            Ok(Ratio {
+                numerator,
+                denominator,
+            })
+        };
+        let images = (RESOLUTION_HEADER_SIZE..value.len())
+            .step_by(IMAGE_SCALING_SIZE)
+            .map(|offset| {
+                let id = read_be_u32(value, offset)? as i32;
+                let scaling = ImageScaling {
+                    ratio: ratio(offset + 4)?,
+                    min: ratio(offset + 12)?,
+                    max: ratio(offset + 20)?,
+                };
+                Ok((id, scaling))
+            })
+            .collect::<Result<_, FileReadError>>()?;
+        Ok(Resolution {
+            standard: size(0)?,
+            min: size(8)?,
+            max: size(16)?,
+            images,
+        })
+    }
+}
+
+impl Resolution {
+    /// How many times larger than the standard size a `width` by `height` window is, going by
+    /// whichever dimension has less room.
+    pub fn elbow_room(&self, width: u32, height: u32) -> f64 {
+        let horizontal = width as f64 / self.standard.0.max(1) as f64;
+        let vertical = height as f64 / self.standard.1.max(1) as f64;
+        horizontal.min(vertical)
+    }
+
+    /// The scale picture `id` is drawn at in a `width` by `height` window. Pictures the chunk
+    /// doesn't list are never scaled.
+    pub fn scale(&self, id: i32, width: u32, height: u32) -> f64 {
+        self.images
+            .get(&id)
+            .map_or(1.0, |scaling| scaling.scale(self.elbow_room(width, height)))
+    }
+}
+
+/// The colours the `Plte` chunk says the game's pictures use.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Palette {
+    /// The game wants a direct colour display with this many bits per pixel, either 16 or 32,
+    /// rather than a palette.
+    DirectColour(u8),
+    /// Red, green and blue for each colour.
+    Colours(Vec<[u8; 3]>),
+}
+
+/// Reads the data of a `Plte` chunk: either a single byte giving the direct colour depth, or a
+/// list of RGB triples.
+impl TryFrom<&[u8]> for Palette {
+    type Error = FileReadError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        match value {
+            [bits @ (16 | 32)] => Ok(Palette::DirectColour(*bits)),
+            [bits] => Err(FileReadError::new(InvalidColourDepth(*bits)).at(0)),
+            _ if !value.len().is_multiple_of(3) => {
+                let expected = value.len() - value.len() % 3;
+                Err(FileReadError::new(InvalidLength(value.len(), expected)).at(expected))
+            }
+            _ => Ok(Palette::Colours(
+                value
+                    .chunks_exact(3)
+                    .map(|rgb| [rgb[0], rgb[1], rgb[2]])
+                    .collect(),
+            )),
+        }
+    }
+}
+
+impl Palette {
+    /// Whether `rgb` is one of the palette's colours. Direct colour allows any colour.
+    pub fn contains(&self, rgb: [u8; 3]) -> bool {
+        match self {
+            Palette::DirectColour(_) => true,
+            Palette::Colours(colours) => colours.contains(&rgb),
+        }
+    }
+}
+
+impl Display for Palette {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Palette::DirectColour(bits) => write!(f, "Direct colour, {bits} bits per pixel"),
+            Palette::Colours(colours) => write!(f, "{} colours", colours.len()),
+        }
     }
 }
 
@@ -93,23 +523,55 @@ impl<'a> TryFrom<&'a [u8]> for BlorbReader<'a> {
     type Error = FileReadError;
 
     fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
-        if read_be_u32(&value[..4]) != BlorbChunkType::FORM as u32 {
-            return Err(UnexpectedStartingIdentifier(BlorbChunkType::FORM));
+        if read_be_u32(value, 0)? != BlorbChunkType::FORM as u32 {
+            return Err(
+                FileReadError::new(UnexpectedStartingIdentifier(BlorbChunkType::FORM)).at(0),
+            );
         }
-        if read_be_u32(&value[4..8]) != (value.len() - 8) as u32 {
-            return Err(InvalidLength(
-                value.len() - 8,
-                read_be_u32(&value[4..8]) as usize,
-            ));
+        let form_len = read_be_u32(value, 4)?;
+        if form_len != (value.len() - 8) as u32 {
+            return Err(
+                FileReadError::new(InvalidLength(value.len() - 8, form_len as usize)).at(4),
+            );
         }
-        if read_be_u32(&value[8..12]) != BlorbChunkType::IFRS as u32 {
-            return Err(UnexpectedStartingIdentifier(BlorbChunkType::IFRS));
+        if read_be_u32(value, 8)? != BlorbChunkType::IFRS as u32 {
+            return Err(
+                FileReadError::new(UnexpectedStartingIdentifier(BlorbChunkType::IFRS)).at(8),
+            );
         }
 
-        let file_index = value[12..].try_into()?;
+        let file_index = FileIndex::try_from(value).map_err(|e| {
+            e.context(InvalidResourceIndex)
+                .at(INDEX_OFFSET)
+                .in_chunk(BlorbChunkType::RESOURCE_INDEX)
+        })?;
+
+        let (chunks, chunk_error) = read_chunks(value);
+
+        Ok(BlorbReader {
+            file_index,
+            chunks,
+            chunk_error,
+        })
+    }
+}
 
-        Ok(BlorbReader { file_index })
+/// Walks every chunk in the FORM, starting after the IFRS form type. Each chunk is padded to an
+/// even length, and the pad byte isn't counted in the chunk's length. The resources were already
+/// found through the index, so a chunk that can't be read only stops the walk, and the chunks
+/// before it are returned along with the error.
+fn read_chunks(file: &[u8]) -> (Vec<Chunk<'_>>, Option<FileReadError>) {
+    let mut chunks = Vec::new();
+    let mut offset = INDEX_OFFSET;
+    while offset < file.len() {
+        let chunk = match Chunk::at(file, offset) {
+            Ok(chunk) => chunk,
+            Err(e) => return (chunks, Some(e.context(InvalidChunk).at(offset))),
+        };
+        offset += 8 + chunk.data.len() + (chunk.data.len() & 1);
+        chunks.push(chunk);
     }
+    (chunks, None)
 }
 
 impl<'a> BlorbReader<'a> {
@@ -119,53 +581,333 @@ impl<'a> BlorbReader<'a> {
 
     pub fn exec_ids(&'a self) -> Vec<i32> {
         self.file_index
-            .0
+            .resources
             .get(&BlorbChunkType::EXECUTABLE)
             .map(|m| m.keys().cloned().collect())
-            .unwrap_or(Vec::new())
+            .unwrap_or_default()
     }
 
+    /// Executable `id` read as Glulx, or `None` if there's no such executable, it's another
+    /// format, or it can't be read.
     pub fn get_exec(&'a self, id: i32) -> Option<UlxReader<'a>> {
         let c = self
             .file_index
-            .0
+            .resources
+            .get(&BlorbChunkType::EXECUTABLE)?
+            .get(&id)?;
+        if c.chunk_type != BlorbChunkType::EXEC_GLUL {
+            return None;
+        }
+        c.data.try_into().ok()
+    }
+
+    /// Executable `id` read as Z-code, or `None` if there's no such executable, it's another
+    /// format, or it can't be read.
+    pub fn get_zcode(&'a self, id: i32) -> Option<ZcodeReader<'a>> {
+        let c = self
+            .file_index
+            .resources
             .get(&BlorbChunkType::EXECUTABLE)?
             .get(&id)?;
+        if c.chunk_type != BlorbChunkType::EXEC_ZCOD {
+            return None;
+        }
         c.data.try_into().ok()
     }
 
     pub fn image_ids(&'a self) -> Vec<i32> {
         self.file_index
-            .0
+            .resources
             .get(&BlorbChunkType::PICTURE)
             .map(|m| m.keys().cloned().collect())
-            .unwrap_or(Vec::new())
+            .unwrap_or_default()
     }
 
     pub fn get_image(&'a self, id: i32) -> Option<&'a Chunk<'a>> {
         self.file_index
-            .0
+            .resources
             .get(&BlorbChunkType::PICTURE)
             .and_then(|hm| hm.get(&id))
     }
 
+    /// The picture resource the `Fspc` chunk names as the cover art, if there is one.
+    pub fn frontispiece_id(&self) -> Option<i32> {
+        let chunk = self.find_chunk(BlorbChunkType::FRONTISPIECE)?;
+        read_be_u32(chunk.data, 0).ok().map(|id| id as i32)
+    }
+
+    /// Picture `id` read as a placeholder, or `None` if there's no such picture or it's an image.
+    pub fn get_placeholder(&'a self, id: i32) -> Result<Option<Placeholder>, FileReadError> {
+        self.get_image(id)
+            .filter(|chunk| chunk.chunk_type == BlorbChunkType::PICTURE_RECT)
+            .map(|chunk| {
+                Placeholder::try_from(chunk.data).map_err(|e| {
+                    e.offset_by(chunk.offset + 8)
+                        .context(InvalidResource)
+                        .at(chunk.offset)
+                        .in_chunk(BlorbChunkType::PICTURE_RECT)
+                        .for_resource(id)
+                })
+            })
+            .transpose()
+    }
+
+    /// The cover art picture, along with its resource ID. `None` if there's no `Fspc` chunk or it
+    /// names a picture the resource index doesn't have.
+    pub fn frontispiece(&'a self) -> Option<(i32, &'a Chunk<'a>)> {
+        let id = self.frontispiece_id()?;
+        self.get_image(id).map(|chunk| (id, chunk))
+    }
+
+    pub fn get_sound(&self, id: i32) -> Option<&Chunk<'a>> {
+        self.file_index
+            .resources
+            .get(&BlorbChunkType::SOUND)
+            .and_then(|hm| hm.get(&id))
+    }
+
+    /// Sound `id` read as an AIFF, or `None` if there's no such sound or it's in another format.
+    pub fn get_aiff(&'a self, id: i32) -> Result<Option<AiffSound<'a>>, FileReadError> {
+        self.get_sound(id)
+            .filter(|chunk| chunk.chunk_type == BlorbChunkType::SOUND_AIFF)
+            .map(|chunk| {
+                AiffSound::try_from(chunk.data).map_err(|e| {
+                    e.offset_by(chunk.offset + 8)
+                        .context(InvalidSound)
+                        .at(chunk.offset)
+                        .in_chunk(BlorbChunkType::SOUND_AIFF)
+                        .for_resource(id)
+                })
+            })
+            .transpose()
+    }
+
+    /// Sound `id` read as Ogg Vorbis, or `None` if there's no such sound or it's in another
+    /// format.
+    pub fn get_ogg(&'a self, id: i32) -> Result<Option<VorbisSound<'a>>, FileReadError> {
+        self.get_sound(id)
+            .filter(|chunk| chunk.chunk_type == BlorbChunkType::SOUND_OGG)
+            .map(|chunk| {
+                VorbisSound::try_from(chunk.data).map_err(|e| {
+                    e.offset_by(chunk.offset + 8)
+                        .context(InvalidSound)
+                        .at(chunk.offset)
+                        .in_chunk(BlorbChunkType::SOUND_OGG)
+                        .for_resource(id)
+                })
+            })
+            .transpose()
+    }
+
+    /// Sound `id` read as a MOD module, or `None` if there's no such sound or it's in another
+    /// format.
+    pub fn get_mod(&self, id: i32) -> Result<Option<TrackerModule<'a>>, FileReadError> {
+        self.get_sound(id)
+            .filter(|chunk| chunk.chunk_type == BlorbChunkType::SOUND_MOD)
+            .map(|chunk| {
+                TrackerModule::try_from(chunk.data).map_err(|e| {
+                    e.offset_by(chunk.offset + 8)
+                        .context(InvalidSound)
+                        .at(chunk.offset)
+                        .in_chunk(BlorbChunkType::SOUND_MOD)
+                        .for_resource(id)
+                })
+            })
+            .transpose()
+    }
+
+    /// Sound `id` read as a `SONG`, or `None` if there's no such sound or it's in another format.
+    /// Its samples are resolved with `song_samples`.
+    pub fn get_song(&self, id: i32) -> Result<Option<TrackerModule<'a>>, FileReadError> {
+        self.get_sound(id)
+            .filter(|chunk| chunk.chunk_type == BlorbChunkType::SOUND_SONG)
+            .map(|chunk| {
+                TrackerModule::without_sample_data(chunk.data).map_err(|e| {
+                    e.offset_by(chunk.offset + 8)
+                        .context(InvalidSound)
+                        .at(chunk.offset)
+                        .in_chunk(BlorbChunkType::SOUND_SONG)
+                        .for_resource(id)
+                })
+            })
+            .transpose()
+    }
+
+    /// The sound resource each of a `SONG`'s samples refers to.
+    pub fn song_samples(&self, song: &TrackerModule) -> Vec<SongSample> {
+        song.samples
+            .iter()
+            .map(|sample| match sample.sound_id() {
+                None => SongSample::Unused,
+                Some(id) => match self.get_sound(id) {
+                    None => SongSample::Missing(id),
+                    Some(chunk) if chunk.chunk_type == BlorbChunkType::SOUND_AIFF => {
+                        SongSample::Aiff(id)
+                    }
+                    Some(_) => SongSample::NotAiff(id),
+                },
+            })
+            .collect()
+    }
+
     pub fn sound_ids(&'a self) -> Vec<i32> {
         self.file_index
-            .0
+            .resources
             .get(&BlorbChunkType::SOUND)
             .map(|m| m.keys().cloned().collect())
-            .unwrap_or(Vec::new())
+            .unwrap_or_default()
     }
 
     pub fn get_ids(&self, chunk_type: BlorbChunkType) -> Vec<i32> {
         self.file_index
-            .0
+            .resources
             .get(&chunk_type)
             .map(|m| m.keys().cloned().collect())
-            .unwrap_or(Vec::new())
+            .unwrap_or_default()
     }
 
     pub fn get(&'a self, chunk_type: BlorbChunkType, id: i32) -> Option<&'a [u8]> {
-        Some(&self.file_index.0.get(&chunk_type)?.get(&id)?.data)
+        Some(self.file_index.resources.get(&chunk_type)?.get(&id)?.data)
+    }
+
+    /// Every top-level chunk in file order, including the resource index itself and chunks the
+    /// index doesn't mention.
+    pub fn chunks(&self) -> &[Chunk<'a>] {
+        &self.chunks
+    }
+
+    /// Why `chunks` stops short of the end of the file, if it does.
+    pub fn chunk_error(&self) -> Option<&FileReadError> {
+        self.chunk_error.as_ref()
+    }
+
+    /// The first top-level chunk of `chunk_type`, for the optional chunks that appear at most
+    /// once, such as `IFmd` or `Fspc`.
+    pub fn find_chunk(&self, chunk_type: BlorbChunkType) -> Option<&Chunk<'a>> {
+        self.chunks.iter().find(|c| c.chunk_type == chunk_type)
+    }
+
+    /// The iFiction record from the `IFmd` chunk, or `None` if the file doesn't have one.
+    pub fn metadata(&self) -> Result<Option<IFictionMetadata>, FileReadError> {
+        self.find_chunk(BlorbChunkType::IFICTION_METADATA)
+            .map(|chunk| {
+                IFictionMetadata::try_from(chunk.data).map_err(|e| {
+                    e.offset_by(chunk.offset + 8)
+                        .in_chunk(BlorbChunkType::IFICTION_METADATA)
+                })
+            })
+            .transpose()
+    }
+
+    /// The palette from the `Plte` chunk, or `None` if the file doesn't have one.
+    pub fn palette(&self) -> Result<Option<Palette>, FileReadError> {
+        self.find_chunk(BlorbChunkType::COLOR_PALETTE)
+            .map(|chunk| {
+                Palette::try_from(chunk.data).map_err(|e| {
+                    e.offset_by(chunk.offset + 8)
+                        .in_chunk(BlorbChunkType::COLOR_PALETTE)
+                })
+            })
+            .transpose()
+    }
+
+    /// The window sizes and picture scaling from the `Reso` chunk, or `None` if the file doesn't
+    /// have one.
+    pub fn resolution(&self) -> Result<Option<Resolution>, FileReadError> {
+        self.find_chunk(BlorbChunkType::RESOLUTION)
+            .map(|chunk| {
+                Resolution::try_from(chunk.data).map_err(|e| {
+                    e.offset_by(chunk.offset + 8)
+                        .in_chunk(BlorbChunkType::RESOLUTION)
+                })
+            })
+            .transpose()
+    }
+
+    /// The resource descriptions from the `RDes` chunk. Files without one have no descriptions,
+    /// rather than an error.
+    pub fn resource_descriptions(&self) -> Result<ResourceDescriptions<'a>, FileReadError> {
+        match self.find_chunk(BlorbChunkType::RESOURCE_DESCRIPTION) {
+            Some(chunk) => ResourceDescriptions::try_from(chunk.data).map_err(|e| {
+                e.offset_by(chunk.offset + 8)
+                    .in_chunk(BlorbChunkType::RESOURCE_DESCRIPTION)
+            }),
+            None => Ok(ResourceDescriptions::default()),
+        }
+    }
+
+    /// The sound repeat counts from the `Loop` chunk. Files without one play every sound once.
+    pub fn loops(&self) -> Result<Loop, FileReadError> {
+        match self.find_chunk(BlorbChunkType::LOOP) {
+            Some(chunk) => Loop::try_from(chunk.data)
+                .map_err(|e| e.offset_by(chunk.offset + 8).in_chunk(BlorbChunkType::LOOP)),
+            None => Ok(Loop::default()),
+        }
+    }
+
+    /// The usage and ID the resource index gives the chunk starting at `offset`, if any.
+    pub fn resource_at(&self, offset: usize) -> Option<(BlorbChunkType, i32)> {
+        self.file_index
+            .resources
+            .iter()
+            .find_map(|(usage, chunks)| {
+                chunks
+                    .iter()
+                    .find(|(_, chunk)| chunk.offset == offset)
+                    .map(|(&id, _)| (*usage, id))
+            })
+    }
+
+    /// Resources whose usage or chunk type the reader doesn't recognise, sorted by usage and ID.
+    pub fn other_chunks(&self) -> Vec<OtherChunk<'_>> {
+        let mut ret: Vec<OtherChunk> = self
+            .file_index
+            .resources
+            .iter()
+            .flat_map(|(usage, chunks)| {
+                chunks
+                    .iter()
+                    .filter(|(_, chunk)| chunk.chunk_type == BlorbChunkType::UNKNOWN)
+                    .map(|(&id, chunk)| OtherChunk {
+                        usage: *usage as u32,
+                        id,
+                        chunk,
+                    })
+            })
+            .chain(
+                self.file_index
+                    .unknown_usage
+                    .iter()
+                    .map(|(usage, id, chunk)| OtherChunk {
+                        usage: *usage,
+                        id: *id,
+                        chunk,
+                    }),
+            )
+            .collect();
+        ret.sort_by_key(|c| (fourcc_to_string(c.usage), c.id));
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_loop_repeats() {
+        let data = [0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 5, 0, 0, 0, 4];
+        let loops = Loop::try_from(&data[..]).unwrap();
+        assert_eq!(loops.repeats(3), Repeats::Forever);
+        assert_eq!(loops.repeats(5), Repeats::Times(4));
+        assert_eq!(loops.repeats(7), Repeats::Times(1));
+        assert_eq!(loops.repeats(5).to_string(), "plays 4 times");
+        assert_eq!(loops.unknown_ids(&[3, 7]), vec![5]);
+    }
+
+    #[test]
+    fn partial_loop_entry_is_error() {
+        let data = [0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 5];
+        assert!(Loop::try_from(&data[..]).is_err());
     }
 }
@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::strings::{push_code_point, StringTypes};
+
+use super::FileReadErrorKind::{InvalidConversion, LeafRoot, UnknownNodeType, UnknownStringType};
+use super::{read_be_u32, read_u8, FileReadError};
+
+/// How many string objects an indirect reference may chain through before decoding gives up.
+/// Real games nest one or two levels deep; anything beyond this is a cycle.
+const MAX_INDIRECTION_DEPTH: usize = 8;
+
+/// The Huffman tree a Glulx game uses to compress its 0xE1 strings, read from
+/// `GlulxHeader::decoding_table_address`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DecodingTable<'a> {
+    memory: &'a [u8],
+    pub address: u32,
+    pub length: u32,
+    pub node_count: u32,
+    pub root: u32,
+    nodes: HashMap<u32, DecodingNode>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum DecodingNode {
+    /// Follow `left` on a 0 bit and `right` on a 1 bit.
+    Branch {
+        left: u32,
+        right: u32,
+    },
+    StringTerminator,
+    /// A single Latin-1 character.
+    Char(u8),
+    /// A run of Latin-1 characters.
+    CString(Vec<u8>),
+    UnicodeChar(u32),
+    UnicodeString(Vec<u32>),
+    /// A string or function at `address`, called with `args` if it is a function.
+    Indirect {
+        address: u32,
+        args: Vec<u32>,
+    },
+    /// As `Indirect`, but `address` holds the address of the object rather than the object.
+    DoubleIndirect {
+        address: u32,
+        args: Vec<u32>,
+    },
+}
+
+impl<'a> DecodingTable<'a> {
+    pub fn new(memory: &'a [u8], address: u32) -> Result<DecodingTable<'a>, FileReadError> {
+        let start = address as usize;
+        let length = read_be_u32(memory, start)?;
+        let node_count = read_be_u32(memory, start + 4)?;
+        let root = read_be_u32(memory, start + 8)?;
+
+        let mut nodes = HashMap::new();
+        let mut to_visit = vec![root];
+        while let Some(node_address) = to_visit.pop() {
+            if nodes.contains_key(&node_address) {
+                continue;
+            }
+            let node = read_node(memory, node_address as usize)?;
+            if let DecodingNode::Branch { left, right } = node {
+                to_visit.push(left);
+                to_visit.push(right);
+            }
+            nodes.insert(node_address, node);
+        }
+
+        // A leaf at the root would emit characters forever without consuming any bits.
+        match nodes[&root] {
+            DecodingNode::Branch { .. } | DecodingNode::StringTerminator => {}
+            _ => return Err(FileReadError::new(LeafRoot).at(root as usize)),
+        }
+
+        Ok(DecodingTable {
+            memory,
+            address,
+            length,
+            node_count,
+            root,
+            nodes,
+        })
+    }
+
+    /// The addresses of every object the table's indirect nodes refer to, with double-indirect
+    /// references followed through to their target.
+    pub fn references(&self) -> Vec<u32> {
+        self.nodes
+            .values()
+            .filter_map(|node| match node {
+                DecodingNode::Indirect { address, .. } => Some(*address),
+                DecodingNode::DoubleIndirect { address, .. } => {
+                    read_be_u32(self.memory, *address as usize).ok()
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Decodes a compressed string. `data` starts immediately after the 0xE1 type byte.
+    pub fn decode(&self, data: &[u8]) -> Result<String, FileReadError> {
+        let mut ret = String::new();
+        self.decode_into(data, &mut ret, 0)?;
+        Ok(ret)
+    }
+
+    fn decode_into(
+        &self,
+        data: &[u8],
+        out: &mut String,
+        depth: usize,
+    ) -> Result<(), FileReadError> {
+        let mut bit = 0usize;
+        let mut node = &self.nodes[&self.root];
+        loop {
+            match node {
+                DecodingNode::Branch { left, right } => {
+                    let byte = read_u8(data, bit / 8)?;
+                    let next = if byte & (1 << (bit % 8)) == 0 {
+                        left
+                    } else {
+                        right
+                    };
+                    bit += 1;
+                    node = &self.nodes[next];
+                    continue;
+                }
+                DecodingNode::StringTerminator => return Ok(()),
+                DecodingNode::Char(c) => out.push(char::from(*c)),
+                DecodingNode::CString(s) => out.extend(s.iter().map(|&c| char::from(c))),
+                DecodingNode::UnicodeChar(c) => push_code_point(out, *c),
+                DecodingNode::UnicodeString(s) => s.iter().for_each(|&c| push_code_point(out, c)),
+                DecodingNode::Indirect { address, args } => {
+                    self.decode_reference(*address, args, out, depth)?
+                }
+                DecodingNode::DoubleIndirect { address, args } => {
+                    let target = read_be_u32(self.memory, *address as usize)?;
+                    self.decode_reference(target, args, out, depth)?
+                }
+            }
+            node = &self.nodes[&self.root];
+        }
+    }
+
+    /// Prints the string at `address`, or a placeholder if it refers to a function that the
+    /// interpreter would call at this point.
+    fn decode_reference(
+        &self,
+        address: u32,
+        args: &[u32],
+        out: &mut String,
+        depth: usize,
+    ) -> Result<(), FileReadError> {
+        let start = address as usize;
+        let type_byte = read_u8(self.memory, start)?;
+        match type_byte {
+            0xE1 if depth < MAX_INDIRECTION_DEPTH => {
+                self.decode_into(&self.memory[start + 1..], out, depth + 1)
+            }
+            0xE0 | 0xE2 => {
+                let string_type = StringTypes::try_from(type_byte)
+                    .map_err(|_| FileReadError::new(UnknownStringType(type_byte)).at(start))?;
+                out.push_str(&string_type.parse(&self.memory[start + 1..], Some(self)));
+                Ok(())
+            }
+            0xC0 | 0xC1 if args.is_empty() => {
+                write!(out, "[call 0x{address:X}]").map_err(|_| InvalidConversion.into())
+            }
+            0xC0 | 0xC1 => {
+                let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+                write!(out, "[call 0x{address:X}({})]", args.join(", "))
+                    .map_err(|_| InvalidConversion.into())
+            }
+            _ => write!(out, "[object 0x{address:X}]").map_err(|_| InvalidConversion.into()),
+        }
+    }
+}
+
+fn read_node(memory: &[u8], start: usize) -> Result<DecodingNode, FileReadError> {
+    let read_word = |offset: usize| read_be_u32(memory, offset);
+    let read_args = |offset: usize| -> Result<Vec<u32>, FileReadError> {
+        let count = read_word(offset)? as usize;
+        (0..count).map(|i| read_word(offset + 4 + i * 4)).collect()
+    };
+    let node_type = read_u8(memory, start)?;
+    let body = start + 1;
+    Ok(match node_type {
+        0x00 => DecodingNode::Branch {
+            left: read_word(body)?,
+            right: read_word(body + 4)?,
+        },
+        0x01 => DecodingNode::StringTerminator,
+        0x02 => DecodingNode::Char(read_u8(memory, body)?),
+        0x03 => {
+            let len = memory[body.min(memory.len())..]
+                .iter()
+                .position(|&b| b == 0)
+                .ok_or(FileReadError::new(super::FileReadErrorKind::OutOfBounds).at(body))?;
+            DecodingNode::CString(memory[body..body + len].to_vec())
+        }
+        0x04 => DecodingNode::UnicodeChar(read_word(body)?),
+        0x05 => {
+            let mut chars = Vec::new();
+            loop {
+                match read_word(body + chars.len() * 4)? {
+                    0 => break,
+                    c => chars.push(c),
+                }
+            }
+            DecodingNode::UnicodeString(chars)
+        }
+        0x08 => DecodingNode::Indirect {
+            address: read_word(body)?,
+            args: Vec::new(),
+        },
+        0x09 => DecodingNode::DoubleIndirect {
+            address: read_word(body)?,
+            args: Vec::new(),
+        },
+        0x0A => DecodingNode::Indirect {
+            address: read_word(body)?,
+            args: read_args(body + 4)?,
+        },
+        0x0B => DecodingNode::DoubleIndirect {
+            address: read_word(body)?,
+            args: read_args(body + 4)?,
+        },
+        _ => return Err(FileReadError::new(UnknownNodeType(node_type)).at(start)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A decoding table at address 0 and a string compressed with it at 69. The codes, read from
+    /// the least significant bit of each byte, are: 00 end, 01 "a", 10 "bc", 110 "€" and 111 the
+    /// C string at 65.
+    #[rustfmt::skip]
+    const MEMORY: [u8; 72] = [
+        0, 0, 0, 65, 0, 0, 0, 9, 0, 0, 0, 12, // length, node count, root
+        0x00, 0, 0, 0, 21, 0, 0, 0, 30, // 12: branch
+        0x00, 0, 0, 0, 39, 0, 0, 0, 40, // 21: branch
+        0x00, 0, 0, 0, 42, 0, 0, 0, 46, // 30: branch
+        0x01, // 39: end of string
+        0x02, b'a', // 40: character
+        0x03, b'b', b'c', 0, // 42: C string
+        0x00, 0, 0, 0, 55, 0, 0, 0, 60, // 46: branch
+        0x04, 0, 0, 0x20, 0xAC, // 55: Unicode character
+        0x08, 0, 0, 0, 65, // 60: indirect reference
+        0xE0, b'h', b'i', 0, // 65: C string
+        0xE1, 0b1011_0110, 0b0000_1011, // 69: "a", "bc", "€", the string at 65, "a", end
+    ];
+
+    #[test]
+    fn decodes_compressed_string() {
+        let table = DecodingTable::new(&MEMORY, 0).unwrap();
+        assert_eq!(table.root, 12);
+        assert_eq!(table.references(), vec![65]);
+        assert_eq!(table.decode(&MEMORY[70..]).unwrap(), "abc€hia");
+    }
+
+    #[test]
+    fn string_without_terminator_is_error() {
+        let table = DecodingTable::new(&MEMORY, 0).unwrap();
+        assert!(table.decode(&MEMORY[70..71]).is_err());
+    }
+}
@@ -0,0 +1,320 @@
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+
+use super::disassembler::Disassembler;
+use super::ulx_reader::UlxReader;
+use super::zcode_reader::ZcodeReader;
+use super::zscii::ZsciiDecoder;
+use super::FileReadErrorKind::{InvalidDictionary, InvalidLength};
+use super::{read_be_u16, read_be_u32, read_bytes, read_u8, FileReadError};
+
+/// The words a game's parser understands, from a Z-code dictionary or an Inform Glulx
+/// `#dictionary_table`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Dictionary {
+    /// Where the table starts: the separator count for Z-code, the word count for Glulx.
+    pub address: usize,
+    pub location: DictionaryLocation,
+    /// The characters besides space that split the player's input into words. Glulx games keep
+    /// these in the parser rather than the dictionary, so it's empty for them.
+    pub word_separators: Vec<char>,
+    /// Bytes per entry, including the word.
+    pub entry_length: usize,
+    /// Bytes of each entry the word takes up.
+    pub word_length: usize,
+    pub entries: Vec<DictionaryEntry>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct DictionaryEntry {
+    pub address: usize,
+    pub word: String,
+    /// The bytes after the word, which Inform uses for flags, a verb number and a third value.
+    pub data: Vec<u8>,
+    /// Inform's `#dict_par1`: the first data byte for Z-code, or the first data word for Glulx.
+    pub flags: u16,
+}
+
+/// How the dictionary was found.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum DictionaryLocation {
+    /// The Z-machine header's dictionary field.
+    Header,
+    /// A constant operand in the game's code, as `#dictionary_table` compiles to.
+    Operand,
+    /// A scan of memory for something laid out like a dictionary.
+    Scan,
+}
+
+impl Display for DictionaryLocation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DictionaryLocation::Header => f.write_str("the header"),
+            DictionaryLocation::Operand => f.write_str("a code operand"),
+            DictionaryLocation::Scan => f.write_str("a scan of memory"),
+        }
+    }
+}
+
+/// Inform's names for the bits of `#dict_par1`.
+const INFORM_FLAGS: [(u16, &str); 5] = [
+    (1, "verb"),
+    (2, "meta"),
+    (4, "plural"),
+    (8, "preposition"),
+    (128, "noun"),
+];
+
+impl DictionaryEntry {
+    /// The names Inform gives the flags that are set, with the bits it doesn't name as numbers.
+    pub fn flag_names(&self) -> Vec<String> {
+        (0..16)
+            .map(|bit| 1 << bit)
+            .filter(|&flag| self.flags & flag != 0)
+            .map(|flag| match INFORM_FLAGS.iter().find(|(f, _)| *f == flag) {
+                Some((_, name)) => name.to_string(),
+                None => format!("bit {}", flag.trailing_zeros()),
+            })
+            .collect()
+    }
+}
+
+impl TryFrom<&ZcodeReader<'_>> for Dictionary {
+    type Error = FileReadError;
+
+    fn try_from(reader: &ZcodeReader<'_>) -> Result<Self, Self::Error> {
+        let address = reader.header.dictionary as usize;
+        read_zcode_dictionary(reader, address).map_err(|e| e.context(InvalidDictionary).at(address))
+    }
+}
+
+fn read_zcode_dictionary(
+    reader: &ZcodeReader,
+    address: usize,
+) -> Result<Dictionary, FileReadError> {
+    let memory = reader.memory;
+    let decoder = ZsciiDecoder::new(reader)?;
+    let separator_count = read_u8(memory, address)? as usize;
+    let word_separators = read_bytes(memory, address + 1, separator_count)?
+        .iter()
+        .map(|&code| {
+            decoder
+                .zscii_char(code as u16)
+                .unwrap_or(char::REPLACEMENT_CHARACTER)
+        })
+        .collect();
+    let lengths = address + 1 + separator_count;
+    let entry_length = read_u8(memory, lengths)? as usize;
+    let word_length = if reader.header.version <= 3 { 4 } else { 6 };
+    if entry_length < word_length {
+        return Err(FileReadError::new(InvalidLength(entry_length, word_length)).at(lengths));
+    }
+    // A negative count means the entries aren't sorted.
+    let count = (read_be_u16(memory, lengths + 1)? as i16).unsigned_abs() as usize;
+    let first_entry = lengths + 3;
+    let entries = (0..count)
+        .map(|i| {
+            let entry = first_entry + i * entry_length;
+            let data = read_bytes(memory, entry + word_length, entry_length - word_length)?;
+            Ok(DictionaryEntry {
+                address: entry,
+                word: decoder.decode_fixed(entry, word_length)?,
+                data: data.to_vec(),
+                flags: data.first().copied().unwrap_or_default() as u16,
+            })
+        })
+        .collect::<Result<_, FileReadError>>()?;
+    Ok(Dictionary {
+        address,
+        location: DictionaryLocation::Header,
+        word_separators,
+        entry_length,
+        word_length,
+        entries,
+    })
+}
+
+/// Inform starts each Glulx dictionary entry with this byte.
+const GLULX_ENTRY_TYPE: u8 = 0x60;
+/// The flags, verb number and third value after each Glulx word, two bytes each.
+const GLULX_ENTRY_DATA: usize = 6;
+/// The longest `$DICT_WORD_SIZE` looked for.
+const MAX_GLULX_WORD_CHARACTERS: usize = 64;
+
+impl Dictionary {
+    /// Looks for the `#dictionary_table` of an Inform Glulx game, which the header doesn't point
+    /// at. The constants in the game's code are tried first, then every address in memory, and
+    /// whichever candidate has the most words wins.
+    pub fn find_glulx(game: &UlxReader) -> Option<Dictionary> {
+        let memory = game.memory;
+        let functions = Disassembler::new(game).disassemble();
+        let constants: BTreeSet<usize> = functions
+            .iter()
+            .flat_map(|function| &function.instructions)
+            .flat_map(|instruction| instruction.constants())
+            .map(|constant| constant as usize)
+            .collect();
+        let best = |candidates: Vec<Dictionary>| {
+            candidates
+                .into_iter()
+                .rev()
+                .max_by_key(|dictionary| dictionary.entries.len())
+        };
+        best(
+            constants
+                .into_iter()
+                .filter_map(|address| {
+                    read_glulx_dictionary(memory, address, DictionaryLocation::Operand)
+                })
+                .collect(),
+        )
+        .or_else(|| {
+            best(
+                (0..memory.len())
+                    .filter_map(|address| {
+                        read_glulx_dictionary(memory, address, DictionaryLocation::Scan)
+                    })
+                    .collect(),
+            )
+        })
+    }
+}
+
+/// The dictionary at `address` if there's one there: a word count followed by that many entries
+/// of the same length, each starting with the entry type byte and holding a zero-padded word.
+fn read_glulx_dictionary(
+    memory: &[u8],
+    address: usize,
+    location: DictionaryLocation,
+) -> Option<Dictionary> {
+    let count = read_be_u32(memory, address).ok()? as usize;
+    let first_entry = address + 4;
+    // It takes two entries to tell how long they are.
+    if count < 2 || memory.get(first_entry) != Some(&GLULX_ENTRY_TYPE) {
+        return None;
+    }
+    let space = memory.len() - first_entry;
+    let shortest = 1 + 1 + GLULX_ENTRY_DATA;
+    let longest = 1 + MAX_GLULX_WORD_CHARACTERS * 4 + GLULX_ENTRY_DATA;
+    (shortest..=longest)
+        .filter(|entry_length| count.checked_mul(*entry_length).is_some_and(|n| n <= space))
+        .find_map(|entry_length| {
+            let entries = read_glulx_entries(memory, first_entry, count, entry_length)?;
+            Some(Dictionary {
+                address,
+                location,
+                word_separators: Vec::new(),
+                entry_length,
+                word_length: entry_length - 1 - GLULX_ENTRY_DATA,
+                entries,
+            })
+        })
+}
+
+fn read_glulx_entries(
+    memory: &[u8],
+    first_entry: usize,
+    count: usize,
+    entry_length: usize,
+) -> Option<Vec<DictionaryEntry>> {
+    let entry = |i: usize| &memory[first_entry + i * entry_length..][..entry_length];
+    if (0..count).any(|i| entry(i)[0] != GLULX_ENTRY_TYPE) {
+        return None;
+    }
+    let word_length = entry_length - 1 - GLULX_ENTRY_DATA;
+    // Games compiled with `$DICT_CHAR_SIZE=4` store each character as a 32-bit code point.
+    let wide = word_length.is_multiple_of(4)
+        && (0..count).all(|i| {
+            entry(i)[1..=word_length]
+                .chunks_exact(4)
+                .all(|c| c[0] == 0 && c[1] == 0)
+        });
+    (0..count)
+        .map(|i| {
+            let bytes = entry(i);
+            let characters: Vec<u32> = if wide {
+                bytes[1..=word_length]
+                    .chunks_exact(4)
+                    .map(|c| u32::from_be_bytes(c.try_into().unwrap()))
+                    .collect()
+            } else {
+                bytes[1..=word_length].iter().map(|&b| b as u32).collect()
+            };
+            let data = &bytes[1 + word_length..];
+            Some(DictionaryEntry {
+                address: first_entry + i * entry_length,
+                word: glulx_word(&characters)?,
+                data: data.to_vec(),
+                flags: u16::from_be_bytes([data[0], data[1]]),
+            })
+        })
+        .collect()
+}
+
+/// The word the characters of an entry spell out, or `None` if they don't look like a word:
+/// empty, holding control characters, or with anything but zeros after the first zero.
+fn glulx_word(characters: &[u32]) -> Option<String> {
+    let length = characters
+        .iter()
+        .position(|&c| c == 0)
+        .unwrap_or(characters.len());
+    if length == 0 || characters[length..].iter().any(|&c| c != 0) {
+        return None;
+    }
+    characters[..length]
+        .iter()
+        .map(|&c| char::from_u32(c).filter(|c| !c.is_control()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DICTIONARY: usize = 0x40;
+
+    /// Packs six Z-characters into the two words of a version 3 dictionary word.
+    fn word(z: [u16; 6]) -> [u8; 4] {
+        let first = z[0] << 10 | z[1] << 5 | z[2];
+        let second = 0x8000 | z[3] << 10 | z[4] << 5 | z[5];
+        let [a, b] = first.to_be_bytes();
+        let [c, d] = second.to_be_bytes();
+        [a, b, c, d]
+    }
+
+    #[test]
+    fn reads_unsorted_zcode_dictionary() {
+        let mut memory = [0; 0x80];
+        memory[0] = 3;
+        memory[0x08..0x0A].copy_from_slice(&(DICTIONARY as u16).to_be_bytes());
+        // Three separators, 7 byte entries and a count of -2.
+        let mut table = vec![3, b'.', b',', b'"', 7, 0xFF, 0xFE];
+        table.extend(word([31, 6, 21, 5, 5, 5]));
+        table.extend([0x81, 0, 1]);
+        table.extend(word([17, 6, 18, 21, 5, 5]));
+        table.extend([0x80, 0, 0]);
+        memory[DICTIONARY..DICTIONARY + table.len()].copy_from_slice(&table);
+
+        let reader = ZcodeReader::try_from(&memory[..]).unwrap();
+        let dictionary = Dictionary::try_from(&reader).unwrap();
+        assert_eq!(dictionary.word_separators, vec!['.', ',', '"']);
+        assert_eq!((dictionary.entry_length, dictionary.word_length), (7, 4));
+        let words: Vec<&str> = dictionary.entries.iter().map(|e| e.word.as_str()).collect();
+        assert_eq!(words, vec!["zap", "lamp"]);
+
+        let zap = &dictionary.entries[0];
+        assert_eq!(zap.address, DICTIONARY + 7);
+        assert_eq!(zap.data, vec![0x81, 0, 1]);
+        assert_eq!(zap.flag_names(), vec!["verb", "noun"]);
+    }
+
+    #[test]
+    fn entry_shorter_than_word_is_error() {
+        let mut memory = [0; 0x80];
+        memory[0] = 3;
+        memory[0x08..0x0A].copy_from_slice(&(DICTIONARY as u16).to_be_bytes());
+        memory[DICTIONARY..DICTIONARY + 4].copy_from_slice(&[0, 3, 0, 1]);
+        let reader = ZcodeReader::try_from(&memory[..]).unwrap();
+        assert!(Dictionary::try_from(&reader).is_err());
+    }
+}
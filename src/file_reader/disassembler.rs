@@ -0,0 +1,592 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+use crate::strings::StringTypes;
+
+use super::decoding_table::DecodingTable;
+use super::ulx_reader::UlxReader;
+use super::FileReadErrorKind::{
+    InvalidLocalsSize, UnknownAddressingMode, UnknownFunctionType, UnknownOpcode,
+};
+use super::{read_be_u16, read_be_u32, read_u8, FileReadError};
+
+/// Longest string shown as a comment next to an operand that refers to it.
+const MAX_COMMENT_LEN: usize = 60;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum FunctionType {
+    /// Arguments are pushed onto the stack followed by the argument count.
+    StackArgs = 0xC0,
+    /// Arguments are copied into the first locals.
+    LocalArgs = 0xC1,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct LocalsFormat {
+    /// Size of each local in bytes: 1, 2 or 4.
+    pub size: u8,
+    pub count: u8,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Function {
+    pub address: u32,
+    pub function_type: FunctionType,
+    pub locals: Vec<LocalsFormat>,
+    pub instructions: Vec<Instruction>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Instruction {
+    pub address: u32,
+    /// Length in bytes, including the opcode and addressing mode bytes.
+    pub length: u32,
+    pub opcode: u32,
+    pub mnemonic: &'static str,
+    pub operands: Vec<Operand>,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum OperandKind {
+    Load,
+    Store,
+    /// A load operand holding a branch offset relative to the end of the instruction.
+    Branch,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum AddressingMode {
+    /// Constant zero when loading, discard when storing.
+    Zero,
+    Constant(i32),
+    Memory(u32),
+    Stack,
+    Local(u32),
+    /// An address relative to `ram_start`.
+    Ram(u32),
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Operand {
+    pub kind: OperandKind,
+    pub mode: AddressingMode,
+}
+
+/// One row of the disassembly listing.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ListingLine {
+    pub address: u32,
+    pub text: String,
+    pub comment: String,
+}
+
+impl Instruction {
+    /// Where a branch operand sends execution, or `None` for a return or computed branch.
+    pub fn branch_target(&self, operand: &Operand) -> Option<u32> {
+        match (operand.kind, operand.mode) {
+            (OperandKind::Branch, AddressingMode::Constant(offset))
+                if offset != 0 && offset != 1 =>
+            {
+                Some(
+                    (self.address + self.length)
+                        .wrapping_add_signed(offset)
+                        .wrapping_sub(2),
+                )
+            }
+            _ => None,
+        }
+    }
+
+    /// Constant load operands, which are the only way code refers to a fixed function or string.
+    pub fn constants(&self) -> impl Iterator<Item = u32> + '_ {
+        self.operands.iter().filter_map(|o| match (o.kind, o.mode) {
+            (OperandKind::Load, AddressingMode::Constant(c)) => Some(c as u32),
+            _ => None,
+        })
+    }
+
+    fn format_operand(&self, operand: &Operand) -> String {
+        match (operand.kind, operand.mode) {
+            (OperandKind::Branch, AddressingMode::Zero | AddressingMode::Constant(0)) => {
+                "-> return 0".to_string()
+            }
+            (OperandKind::Branch, AddressingMode::Constant(1)) => "-> return 1".to_string(),
+            (OperandKind::Branch, AddressingMode::Constant(_)) => {
+                format!("-> 0x{:X}", self.branch_target(operand).unwrap_or(0))
+            }
+            (OperandKind::Store, AddressingMode::Zero) => "discard".to_string(),
+            (_, mode) => mode.to_string(),
+        }
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.mnemonic)?;
+        for operand in &self.operands {
+            write!(f, " {}", self.format_operand(operand))?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for AddressingMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AddressingMode::Zero => f.write_str("0"),
+            AddressingMode::Constant(c) if *c >= 0x100 => write!(f, "0x{c:X}"),
+            AddressingMode::Constant(c) => write!(f, "{c}"),
+            AddressingMode::Memory(address) => write!(f, "*0x{address:X}"),
+            AddressingMode::Stack => f.write_str("sp"),
+            AddressingMode::Local(offset) => write!(f, "local{offset}"),
+            AddressingMode::Ram(offset) => write!(f, "ram[0x{offset:X}]"),
+        }
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let args = match self.function_type {
+            FunctionType::StackArgs => "stack args",
+            FunctionType::LocalArgs => "local args",
+        };
+        let locals: Vec<String> = self
+            .locals
+            .iter()
+            .map(|l| format!("{}x{} bytes", l.count, l.size))
+            .collect();
+        if locals.is_empty() {
+            write!(f, "Function 0x{:X} ({args}, no locals)", self.address)
+        } else {
+            write!(
+                f,
+                "Function 0x{:X} ({args}, locals: {})",
+                self.address,
+                locals.join(", ")
+            )
+        }
+    }
+}
+
+/// Decodes Glulx code, following calls out from the start function.
+pub struct Disassembler<'a> {
+    memory: &'a [u8],
+    ram_start: u32,
+    start_function: u32,
+    decoding_table: Option<DecodingTable<'a>>,
+}
+
+impl<'a> Disassembler<'a> {
+    pub fn new(reader: &UlxReader<'a>) -> Disassembler<'a> {
+        Disassembler {
+            memory: reader.memory,
+            ram_start: reader.header.ram_start,
+            start_function: reader.header.start_function_address,
+            decoding_table: reader.decoding_table(),
+        }
+    }
+
+    /// Every function reachable from the start function, either by a constant operand that
+    /// points at a function header or by directly following another function, ordered by address.
+    pub fn disassemble(&self) -> Vec<Function> {
+        let mut functions = BTreeMap::new();
+        let mut visited = HashSet::new();
+        let mut to_visit = vec![self.start_function];
+        while let Some(address) = to_visit.pop() {
+            if !visited.insert(address) {
+                continue;
+            }
+            let Ok(function) = self.function_at(address) else {
+                continue;
+            };
+            for instruction in &function.instructions {
+                to_visit.extend(
+                    instruction
+                        .constants()
+                        .filter(|&c| self.is_function_header(c)),
+                );
+            }
+            // Functions are laid out back to back, so whatever stopped the sweep may be the next.
+            let end = function
+                .instructions
+                .last()
+                .map(|i| i.address + i.length)
+                .unwrap_or(address);
+            if self.is_function_header(end) {
+                to_visit.push(end);
+            }
+            functions.insert(address, function);
+        }
+        functions.into_values().collect()
+    }
+
+    /// Decodes the function whose header starts at `address`, sweeping instructions until the
+    /// next function or string object, the end of ROM, or an undecodable opcode.
+    pub fn function_at(&self, address: u32) -> Result<Function, FileReadError> {
+        let start = address as usize;
+        let function_type = match self.byte(start)? {
+            0xC0 => FunctionType::StackArgs,
+            0xC1 => FunctionType::LocalArgs,
+            other => return Err(FileReadError::new(UnknownFunctionType(other)).at(start)),
+        };
+        let mut locals = Vec::new();
+        let mut pc = start + 1;
+        loop {
+            let (size, count) = (self.byte(pc)?, self.byte(pc + 1)?);
+            pc += 2;
+            match (size, count) {
+                (0, 0) => break,
+                (1 | 2 | 4, 1..) => locals.push(LocalsFormat { size, count }),
+                _ => return Err(FileReadError::new(InvalidLocalsSize(size)).at(pc - 2)),
+            }
+        }
+
+        let mut instructions = Vec::new();
+        while pc < self.ram_start as usize
+            && !matches!(self.byte(pc), Ok(0xC0..=0xC1 | 0xE0..=0xE2))
+        {
+            match self.instruction_at(pc as u32) {
+                Ok(instruction) => {
+                    pc += instruction.length as usize;
+                    instructions.push(instruction);
+                }
+                Err(_) => break,
+            }
+        }
+        Ok(Function {
+            address,
+            function_type,
+            locals,
+            instructions,
+        })
+    }
+
+    pub fn instruction_at(&self, address: u32) -> Result<Instruction, FileReadError> {
+        let start = address as usize;
+        let first = self.byte(start)?;
+        let (opcode, mut pc) = match first {
+            0x00..=0x7F => (first as u32, start + 1),
+            0x80..=0xBF => (
+                u16::from_be_bytes([first, self.byte(start + 1)?]) as u32 & 0x3FFF,
+                start + 2,
+            ),
+            _ => (self.word(start)? & 0x3FFF_FFFF, start + 4),
+        };
+        let (mnemonic, signature) =
+            opcode_info(opcode).ok_or(FileReadError::new(UnknownOpcode(opcode)).at(start))?;
+
+        let modes: Vec<u8> = (0..signature.len())
+            .map(|i| {
+                self.byte(pc + i / 2)
+                    .map(|b| if i % 2 == 0 { b & 0xF } else { b >> 4 })
+            })
+            .collect::<Result<_, _>>()?;
+        pc += signature.len().div_ceil(2);
+
+        let mut operands = Vec::with_capacity(signature.len());
+        for (kind, mode_nibble) in signature.chars().zip(modes) {
+            let kind = match kind {
+                'S' => OperandKind::Store,
+                'B' => OperandKind::Branch,
+                _ => OperandKind::Load,
+            };
+            let mode = match mode_nibble {
+                0x0 => AddressingMode::Zero,
+                0x1 => AddressingMode::Constant(self.byte(pc)? as i8 as i32),
+                0x2 => AddressingMode::Constant(self.half(pc)? as i16 as i32),
+                0x3 => AddressingMode::Constant(self.word(pc)? as i32),
+                0x5 => AddressingMode::Memory(self.byte(pc)? as u32),
+                0x6 => AddressingMode::Memory(self.half(pc)? as u32),
+                0x7 => AddressingMode::Memory(self.word(pc)?),
+                0x8 => AddressingMode::Stack,
+                0x9 => AddressingMode::Local(self.byte(pc)? as u32),
+                0xA => AddressingMode::Local(self.half(pc)? as u32),
+                0xB => AddressingMode::Local(self.word(pc)?),
+                0xD => AddressingMode::Ram(self.byte(pc)? as u32),
+                0xE => AddressingMode::Ram(self.half(pc)? as u32),
+                0xF => AddressingMode::Ram(self.word(pc)?),
+                _ => return Err(FileReadError::new(UnknownAddressingMode(mode_nibble)).at(start)),
+            };
+            pc += match mode_nibble {
+                0x1 | 0x5 | 0x9 | 0xD => 1,
+                0x2 | 0x6 | 0xA | 0xE => 2,
+                0x3 | 0x7 | 0xB | 0xF => 4,
+                _ => 0,
+            };
+            operands.push(Operand { kind, mode });
+        }
+
+        Ok(Instruction {
+            address,
+            length: (pc - start) as u32,
+            opcode,
+            mnemonic,
+            operands,
+        })
+    }
+
+    /// Flattens `functions` into listing rows, commenting operands that refer to strings or
+    /// functions with what they point at.
+    pub fn listing(&self, functions: &[Function]) -> Vec<ListingLine> {
+        let mut ret = Vec::new();
+        for function in functions {
+            ret.push(ListingLine {
+                address: function.address,
+                text: function.to_string(),
+                comment: String::new(),
+            });
+            for instruction in &function.instructions {
+                let comment: Vec<String> = instruction
+                    .constants()
+                    .filter_map(|c| self.describe_reference(c))
+                    .collect();
+                ret.push(ListingLine {
+                    address: instruction.address,
+                    text: format!("    {instruction}"),
+                    comment: comment.join("; "),
+                });
+            }
+        }
+        ret
+    }
+
+    /// The text of the string object at `address`, if there is one there.
+    pub fn string_at(&self, address: u32) -> Option<(StringTypes, String)> {
+        let start = address as usize;
+        let string_type = StringTypes::try_from(*self.memory.get(start)?).ok()?;
+        let data =
+            string_type.try_parse(&self.memory[start + 1..], self.decoding_table.as_ref())?;
+        Some((string_type, data))
+    }
+
+    fn describe_reference(&self, address: u32) -> Option<String> {
+        if self.is_function_header(address) {
+            return Some(format!("function 0x{address:X}"));
+        }
+        if address < HEADER_END {
+            return None;
+        }
+        let (_, data) = self.string_at(address)?;
+        if data.chars().count() > MAX_COMMENT_LEN {
+            let short: String = data.chars().take(MAX_COMMENT_LEN).collect();
+            Some(format!("{short:?}..."))
+        } else {
+            Some(format!("{data:?}"))
+        }
+    }
+
+    fn is_function_header(&self, address: u32) -> bool {
+        address >= HEADER_END
+            && address < self.ram_start
+            && matches!(self.memory.get(address as usize), Some(0xC0 | 0xC1))
+    }
+
+    fn byte(&self, offset: usize) -> Result<u8, FileReadError> {
+        read_u8(self.memory, offset)
+    }
+
+    fn half(&self, offset: usize) -> Result<u16, FileReadError> {
+        read_be_u16(self.memory, offset)
+    }
+
+    fn word(&self, offset: usize) -> Result<u32, FileReadError> {
+        read_be_u32(self.memory, offset)
+    }
+}
+
+/// Nothing before the end of the Glulx header can be code.
+const HEADER_END: u32 = 36;
+
+/// The mnemonic and operand signature of an opcode. Each signature character is one operand in
+/// order: `L` loads, `S` stores and `B` is a load holding a branch offset.
+fn opcode_info(opcode: u32) -> Option<(&'static str, &'static str)> {
+    Some(match opcode {
+        0x00 => ("nop", ""),
+        0x10 => ("add", "LLS"),
+        0x11 => ("sub", "LLS"),
+        0x12 => ("mul", "LLS"),
+        0x13 => ("div", "LLS"),
+        0x14 => ("mod", "LLS"),
+        0x15 => ("neg", "LS"),
+        0x18 => ("bitand", "LLS"),
+        0x19 => ("bitor", "LLS"),
+        0x1A => ("bitxor", "LLS"),
+        0x1B => ("bitnot", "LS"),
+        0x1C => ("shiftl", "LLS"),
+        0x1D => ("sshiftr", "LLS"),
+        0x1E => ("ushiftr", "LLS"),
+        0x20 => ("jump", "B"),
+        0x22 => ("jz", "LB"),
+        0x23 => ("jnz", "LB"),
+        0x24 => ("jeq", "LLB"),
+        0x25 => ("jne", "LLB"),
+        0x26 => ("jlt", "LLB"),
+        0x27 => ("jge", "LLB"),
+        0x28 => ("jgt", "LLB"),
+        0x29 => ("jle", "LLB"),
+        0x2A => ("jltu", "LLB"),
+        0x2B => ("jgeu", "LLB"),
+        0x2C => ("jgtu", "LLB"),
+        0x2D => ("jleu", "LLB"),
+        0x30 => ("call", "LLS"),
+        0x31 => ("return", "L"),
+        0x32 => ("catch", "SB"),
+        0x33 => ("throw", "LL"),
+        0x34 => ("tailcall", "LL"),
+        0x40 => ("copy", "LS"),
+        0x41 => ("copys", "LS"),
+        0x42 => ("copyb", "LS"),
+        0x44 => ("sexs", "LS"),
+        0x45 => ("sexb", "LS"),
+        0x48 => ("aload", "LLS"),
+        0x49 => ("aloads", "LLS"),
+        0x4A => ("aloadb", "LLS"),
+        0x4B => ("aloadbit", "LLS"),
+        0x4C => ("astore", "LLL"),
+        0x4D => ("astores", "LLL"),
+        0x4E => ("astoreb", "LLL"),
+        0x4F => ("astorebit", "LLL"),
+        0x50 => ("stkcount", "S"),
+        0x51 => ("stkpeek", "LS"),
+        0x52 => ("stkswap", ""),
+        0x53 => ("stkroll", "LL"),
+        0x54 => ("stkcopy", "L"),
+        0x70 => ("streamchar", "L"),
+        0x71 => ("streamnum", "L"),
+        0x72 => ("streamstr", "L"),
+        0x73 => ("streamunichar", "L"),
+        0x100 => ("gestalt", "LLS"),
+        0x101 => ("debugtrap", "L"),
+        0x102 => ("getmemsize", "S"),
+        0x103 => ("setmemsize", "LS"),
+        0x104 => ("jumpabs", "L"),
+        0x110 => ("random", "LS"),
+        0x111 => ("setrandom", "L"),
+        0x120 => ("quit", ""),
+        0x121 => ("verify", "S"),
+        0x122 => ("restart", ""),
+        0x123 => ("save", "LS"),
+        0x124 => ("restore", "LS"),
+        0x125 => ("saveundo", "S"),
+        0x126 => ("restoreundo", "S"),
+        0x127 => ("protect", "LL"),
+        0x128 => ("hasundo", "S"),
+        0x129 => ("discardundo", ""),
+        0x130 => ("glk", "LLS"),
+        0x140 => ("getstringtbl", "S"),
+        0x141 => ("setstringtbl", "L"),
+        0x148 => ("getiosys", "SS"),
+        0x149 => ("setiosys", "LL"),
+        0x150 => ("linearsearch", "LLLLLLLS"),
+        0x151 => ("binarysearch", "LLLLLLLS"),
+        0x152 => ("linkedsearch", "LLLLLLS"),
+        0x160 => ("callf", "LS"),
+        0x161 => ("callfi", "LLS"),
+        0x162 => ("callfii", "LLLS"),
+        0x163 => ("callfiii", "LLLLS"),
+        0x170 => ("mzero", "LL"),
+        0x171 => ("mcopy", "LLL"),
+        0x178 => ("malloc", "LS"),
+        0x179 => ("mfree", "L"),
+        0x180 => ("accelfunc", "LL"),
+        0x181 => ("accelparam", "LL"),
+        0x190 => ("numtof", "LS"),
+        0x191 => ("ftonumz", "LS"),
+        0x192 => ("ftonumn", "LS"),
+        0x198 => ("ceil", "LS"),
+        0x199 => ("floor", "LS"),
+        0x1A0 => ("fadd", "LLS"),
+        0x1A1 => ("fsub", "LLS"),
+        0x1A2 => ("fmul", "LLS"),
+        0x1A3 => ("fdiv", "LLS"),
+        0x1A4 => ("fmod", "LLSS"),
+        0x1A8 => ("sqrt", "LS"),
+        0x1A9 => ("exp", "LS"),
+        0x1AA => ("log", "LS"),
+        0x1AB => ("pow", "LLS"),
+        0x1B0 => ("sin", "LS"),
+        0x1B1 => ("cos", "LS"),
+        0x1B2 => ("tan", "LS"),
+        0x1B3 => ("acos", "LS"),
+        0x1B4 => ("asin", "LS"),
+        0x1B5 => ("atan", "LS"),
+        0x1B6 => ("atan2", "LLS"),
+        0x1C0 => ("jfeq", "LLLB"),
+        0x1C1 => ("jfne", "LLLB"),
+        0x1C2 => ("jflt", "LLB"),
+        0x1C3 => ("jfle", "LLB"),
+        0x1C4 => ("jfgt", "LLB"),
+        0x1C5 => ("jfge", "LLB"),
+        0x1C8 => ("jisnan", "LB"),
+        0x1C9 => ("jisinf", "LB"),
+        0x200 => ("numtod", "LSS"),
+        0x201 => ("dtonumz", "LLS"),
+        0x202 => ("dtonumn", "LLS"),
+        0x203 => ("ftod", "LSS"),
+        0x204 => ("dtof", "LLS"),
+        0x208 => ("dceil", "LLSS"),
+        0x209 => ("dfloor", "LLSS"),
+        0x210 => ("dadd", "LLLLSS"),
+        0x211 => ("dsub", "LLLLSS"),
+        0x212 => ("dmul", "LLLLSS"),
+        0x213 => ("ddiv", "LLLLSS"),
+        0x214 => ("dmodr", "LLLLSS"),
+        0x215 => ("dmodq", "LLLLSS"),
+        0x218 => ("dsqrt", "LLSS"),
+        0x219 => ("dexp", "LLSS"),
+        0x21A => ("dlog", "LLSS"),
+        0x21B => ("dpow", "LLLLSS"),
+        0x220 => ("dsin", "LLSS"),
+        0x221 => ("dcos", "LLSS"),
+        0x222 => ("dtan", "LLSS"),
+        0x223 => ("dacos", "LLSS"),
+        0x224 => ("dasin", "LLSS"),
+        0x225 => ("datan", "LLSS"),
+        0x226 => ("datan2", "LLLLSS"),
+        0x230 => ("jdeq", "LLLLLLB"),
+        0x231 => ("jdne", "LLLLLLB"),
+        0x232 => ("jdlt", "LLLLB"),
+        0x233 => ("jdle", "LLLLB"),
+        0x234 => ("jdgt", "LLLLB"),
+        0x235 => ("jdge", "LLLLB"),
+        0x238 => ("jdisnan", "LLB"),
+        0x239 => ("jdisinf", "LLB"),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_reader::FileReadErrorKind::UnknownOpcode;
+
+    /// A Glulx header and debugging header followed by `code` at address 64.
+    fn game(code: &[u8]) -> Vec<u8> {
+        let mut memory = vec![0; 64];
+        memory[..4].copy_from_slice(b"Glul");
+        memory[36..40].copy_from_slice(b"Info");
+        memory.extend(code);
+        memory
+    }
+
+    #[test]
+    fn reads_each_opcode_length() {
+        // streamchar 0 in the one, two and four byte forms.
+        let memory = game(&[0x70, 0x00, 0x80, 0x70, 0x00, 0xC0, 0x00, 0x00, 0x70, 0x00]);
+        let reader = UlxReader::try_from(&memory[..]).unwrap();
+        let disassembler = Disassembler::new(&reader);
+        for (address, length) in [(64, 2), (66, 3), (69, 5)] {
+            let instruction = disassembler.instruction_at(address).unwrap();
+            assert_eq!(instruction.mnemonic, "streamchar");
+            assert_eq!((instruction.opcode, instruction.length), (0x70, length));
+        }
+    }
+
+    #[test]
+    fn four_byte_opcode_keeps_top_bits() {
+        let memory = game(&[0xD0, 0x00, 0x00, 0x70, 0x00]);
+        let reader = UlxReader::try_from(&memory[..]).unwrap();
+        let error = Disassembler::new(&reader).instruction_at(64).unwrap_err();
+        assert_eq!(error.kind, UnknownOpcode(0x1000_0070));
+    }
+}
@@ -0,0 +1,98 @@
+//! Runs a file through every parser, the way the browser's tabs would. Shared by the fuzz target
+//! and the corpus regression test, so that `cargo test` covers whatever the fuzzer does.
+
+use super::blorb_chunk_types::BlorbChunkType;
+use super::blorb_reader::BlorbReader;
+use super::dictionary::Dictionary;
+use super::disassembler::Disassembler;
+use super::ulx_reader::{StringDiscovery, UlxReader};
+use super::zcode_objects::ObjectTable;
+use super::zcode_reader::ZcodeReader;
+use super::GameType;
+
+/// Parses `data` as a game file and reads everything the parsers can find in it. Errors are
+/// ignored; only panics matter.
+pub fn exercise(data: &[u8]) {
+    match GameType::try_from(data) {
+        Ok(GameType::Ulx(game)) => exercise_exec(&game),
+        Ok(GameType::Zcode(game)) => exercise_zcode(&game),
+        Ok(GameType::Blorb(blorb)) => exercise_blorb(&blorb),
+        Err(_) => {}
+    }
+}
+
+fn exercise_exec(game: &UlxReader) {
+    game.parse_strings(StringDiscovery::Scan);
+    game.integrity_report();
+    let disassembler = Disassembler::new(game);
+    disassembler.listing(&disassembler.disassemble());
+    Dictionary::find_glulx(game);
+}
+
+fn exercise_zcode(game: &ZcodeReader) {
+    game.parse_strings(StringDiscovery::Scan);
+    game.integrity_report();
+    if let Ok(table) = ObjectTable::try_from(game) {
+        for root in table.roots() {
+            table.children(root);
+        }
+        table.misplaced();
+    }
+    let _ = Dictionary::try_from(game);
+}
+
+fn exercise_blorb(blorb: &BlorbReader) {
+    for id in blorb.exec_ids() {
+        if let Some(game) = blorb.get_exec(id) {
+            exercise_exec(&game);
+        }
+        if let Some(game) = blorb.get_zcode(id) {
+            exercise_zcode(&game);
+        }
+    }
+    for chunk_type in [
+        BlorbChunkType::PICTURE,
+        BlorbChunkType::SOUND,
+        BlorbChunkType::DATA,
+    ] {
+        for id in blorb.get_ids(chunk_type) {
+            blorb.get(chunk_type, id);
+        }
+    }
+    blorb.frontispiece();
+    for id in blorb.sound_ids() {
+        if let Ok(Some(aiff)) = blorb.get_aiff(id) {
+            aiff.to_string();
+        }
+        if let Ok(Some(ogg)) = blorb.get_ogg(id) {
+            ogg.to_string();
+        }
+        if let Ok(Some(module)) = blorb.get_mod(id) {
+            module.to_string();
+        }
+        if let Ok(Some(song)) = blorb.get_song(id) {
+            song.to_string();
+            blorb.song_samples(&song);
+        }
+    }
+    let _ = blorb.resource_descriptions();
+    for id in blorb.image_ids() {
+        if let Ok(Some(placeholder)) = blorb.get_placeholder(id) {
+            placeholder.to_string();
+        }
+    }
+    if let Ok(Some(palette)) = blorb.palette() {
+        palette.contains([0, 0, 0]);
+    }
+    if let Ok(Some(resolution)) = blorb.resolution() {
+        for id in blorb.image_ids() {
+            resolution.scale(id, resolution.max.0, resolution.max.1);
+        }
+    }
+    if let Ok(loops) = blorb.loops() {
+        loops.unknown_ids(&blorb.sound_ids());
+    }
+    if let Ok(Some(metadata)) = blorb.metadata() {
+        metadata.fields();
+    }
+}
@@ -0,0 +1,167 @@
+use std::fmt::{Display, Formatter};
+
+use roxmltree::{Document, Node};
+
+use super::FileReadError;
+use super::FileReadErrorKind::InvalidMetadata;
+
+/// The Treaty of Babel iFiction record a Blorb embeds in its `IFmd` chunk. Only the first story in
+/// the record is read, since a Blorb describes a single game.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct IFictionMetadata {
+    pub ifids: Vec<String>,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub headline: Option<String>,
+    pub first_published: Option<String>,
+    pub genre: Option<String>,
+    pub description: Option<String>,
+    pub series: Option<String>,
+    pub series_number: Option<String>,
+    pub language: Option<String>,
+    pub cover: Option<CoverArt>,
+}
+
+/// The `<cover>` element, describing the frontispiece picture rather than containing it.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct CoverArt {
+    pub format: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub description: Option<String>,
+}
+
+impl TryFrom<&[u8]> for IFictionMetadata {
+    type Error = FileReadError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let text = std::str::from_utf8(value)
+            .map_err(|e| FileReadError::new(InvalidMetadata).at(e.valid_up_to()))?;
+        let document = Document::parse(text).map_err(|e| {
+            FileReadError::new(InvalidMetadata).at(byte_offset(text, e.pos().row, e.pos().col))
+        })?;
+        let Some(story) = document
+            .descendants()
+            .find(|n| n.tag_name().name() == "story")
+        else {
+            return Ok(IFictionMetadata::default());
+        };
+
+        let ifids = story
+            .children()
+            .filter(|n| n.tag_name().name() == "identification")
+            .flat_map(|n| n.children())
+            .filter(|n| n.tag_name().name() == "ifid")
+            .map(element_text)
+            .collect();
+        let bibliographic = child(story, "bibliographic");
+        let field = |name: &str| {
+            bibliographic
+                .and_then(|b| child(b, name))
+                .map(element_text)
+                .filter(|text| !text.is_empty())
+        };
+        let cover = child(story, "cover").map(|cover| CoverArt {
+            format: child(cover, "format").map(element_text),
+            width: child(cover, "width").and_then(|n| element_text(n).parse().ok()),
+            height: child(cover, "height").and_then(|n| element_text(n).parse().ok()),
+            description: child(cover, "description").map(element_text),
+        });
+
+        Ok(IFictionMetadata {
+            ifids,
+            title: field("title"),
+            author: field("author"),
+            headline: field("headline"),
+            first_published: field("firstpublished"),
+            genre: field("genre"),
+            description: field("description"),
+            series: field("series"),
+            series_number: field("seriesnumber"),
+            language: field("language"),
+            cover,
+        })
+    }
+}
+
+/// Turns the 1-based line and column, in characters, that roxmltree reports into a byte offset.
+fn byte_offset(text: &str, row: u32, col: u32) -> usize {
+    let line_start: usize = text
+        .split_inclusive('\n')
+        .take(row.saturating_sub(1) as usize)
+        .map(str::len)
+        .sum();
+    text[line_start..]
+        .char_indices()
+        .nth(col.saturating_sub(1) as usize)
+        .map_or(text.len(), |(i, _)| line_start + i)
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|n| n.tag_name().name() == name)
+}
+
+/// All the text inside an element, with the `<br/>` tags iFiction uses to separate paragraphs of
+/// a description turned into line breaks.
+fn element_text(node: Node) -> String {
+    let mut ret = String::new();
+    for n in node.descendants() {
+        if n.is_text() {
+            ret.push_str(n.text().unwrap_or_default());
+        } else if n.tag_name().name() == "br" {
+            ret.push('\n');
+        }
+    }
+    ret.trim().to_string()
+}
+
+impl Display for IFictionMetadata {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (name, value) in self.fields() {
+            writeln!(f, "{name}: {value}")?;
+        }
+        Ok(())
+    }
+}
+
+impl IFictionMetadata {
+    /// The fields that are present, as (label, value) pairs in the order the Treaty lists them.
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        let mut ret = Vec::new();
+        if !self.ifids.is_empty() {
+            ret.push(("IFID", self.ifids.join(", ")));
+        }
+        for (name, value) in [
+            ("Title", &self.title),
+            ("Author", &self.author),
+            ("Headline", &self.headline),
+            ("First published", &self.first_published),
+            ("Genre", &self.genre),
+            ("Language", &self.language),
+            ("Series", &self.series),
+            ("Series number", &self.series_number),
+            ("Description", &self.description),
+        ] {
+            if let Some(value) = value {
+                ret.push((name, value.clone()));
+            }
+        }
+        if let Some(cover) = &self.cover {
+            let size = match (cover.width, cover.height) {
+                (Some(w), Some(h)) => format!(" {w}x{h}"),
+                _ => String::new(),
+            };
+            ret.push((
+                "Cover art",
+                format!(
+                    "{}{size}",
+                    cover.format.as_deref().unwrap_or("unknown format")
+                ),
+            ));
+            if let Some(description) = &cover.description {
+                ret.push(("Cover description", description.clone()));
+            }
+        }
+        ret
+    }
+}
@@ -5,81 +5,369 @@ use std::fmt::{Display, Formatter};
 use blorb_chunk_types::BlorbChunkType;
 use blorb_reader::BlorbReader;
 use ulx_reader::UlxReader;
+use zcode_reader::ZcodeReader;
 
+pub mod aiff;
 pub mod blorb_chunk_types;
 pub mod blorb_reader;
+pub mod decoding_table;
+pub mod dictionary;
+pub mod disassembler;
+#[cfg(any(test, fuzzing))]
+pub mod fuzz_exercise;
+pub mod ifiction;
+pub mod ogg;
+pub mod tracker;
 pub mod ulx_reader;
+pub mod zcode_objects;
+pub mod zcode_reader;
+pub mod zscii;
 
 pub enum GameType<'a> {
     Ulx(UlxReader<'a>),
+    Zcode(ZcodeReader<'a>),
     Blorb(BlorbReader<'a>),
 }
 
 impl<'a> TryFrom<&'a [u8]> for GameType<'a> {
     type Error = FileReadError;
 
+    /// Picks the reader from the file's magic number, so that a damaged file reports why its own
+    /// reader rejected it rather than just that no reader accepted it. Z-code has no magic
+    /// number, so files starting with a Z-machine version number are taken to be Z-code.
     fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
-        if let Ok(ulx) = value.try_into() {
-            Ok(GameType::Ulx(ulx))
-        } else if let Ok(blorb) = value.try_into() {
-            Ok(GameType::Blorb(blorb))
-        } else {
-            Err(FileReadError::UnknownFileType)
+        match read_be_u32(value, 0) {
+            Ok(magic) if magic == BlorbChunkType::FORM as u32 => {
+                Ok(GameType::Blorb(value.try_into()?))
+            }
+            Ok(ulx_reader::GLUL_AS_NUM) => Ok(GameType::Ulx(value.try_into()?)),
+            _ if matches!(value.first(), Some(1..=8)) => Ok(GameType::Zcode(value.try_into()?)),
+            _ => Err(FileReadErrorKind::UnknownFileType.into()),
         }
     }
 }
 
 impl<'a> GameType<'a> {
-    pub fn get_exec(&'a self) -> UlxReader<'a> {
+    /// The Glulx game, if this is one or a Blorb holding one.
+    pub fn get_exec(&'a self) -> Option<UlxReader<'a>> {
+        match self {
+            GameType::Ulx(ulx) => Some(*ulx),
+            GameType::Zcode(_) => None,
+            GameType::Blorb(blorb) => blorb.get_exec(0),
+        }
+    }
+
+    /// The Z-code game, if this is one or a Blorb holding one.
+    pub fn get_zcode(&'a self) -> Option<ZcodeReader<'a>> {
         match self {
-            GameType::Ulx(ulx) => *ulx,
-            GameType::Blorb(blorb) => blorb.get_exec(0).unwrap(),
+            GameType::Ulx(_) => None,
+            GameType::Zcode(zcode) => Some(*zcode),
+            GameType::Blorb(blorb) => blorb.get_zcode(0),
         }
     }
 }
 
-pub(crate) fn read_be_u32(input: &[u8]) -> u32 {
-    u32::from_be_bytes(input[0..4].try_into().unwrap())
+/// Reads the big-endian u32 at `offset`, failing with that offset if it runs past the end.
+pub(crate) fn read_be_u32(input: &[u8], offset: usize) -> Result<u32, FileReadError> {
+    read_bytes(input, offset, 4).map(|b| u32::from_be_bytes(b.try_into().unwrap()))
+}
+
+/// Reads the big-endian u16 at `offset`, failing with that offset if it runs past the end.
+pub(crate) fn read_be_u16(input: &[u8], offset: usize) -> Result<u16, FileReadError> {
+    read_bytes(input, offset, 2).map(|b| u16::from_be_bytes(b.try_into().unwrap()))
+}
+
+/// Reads the little-endian u32 at `offset`, for the formats inside chunks that aren't big-endian.
+pub(crate) fn read_le_u32(input: &[u8], offset: usize) -> Result<u32, FileReadError> {
+    read_bytes(input, offset, 4).map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+}
+
+pub(crate) fn read_le_u64(input: &[u8], offset: usize) -> Result<u64, FileReadError> {
+    read_bytes(input, offset, 8).map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+}
+
+pub(crate) fn read_u8(input: &[u8], offset: usize) -> Result<u8, FileReadError> {
+    input
+        .get(offset)
+        .copied()
+        .ok_or(FileReadError::new(FileReadErrorKind::OutOfBounds).at(offset))
+}
+
+/// The `len` bytes starting at `offset`, failing with that offset if there aren't that many.
+pub(crate) fn read_bytes(input: &[u8], offset: usize, len: usize) -> Result<&[u8], FileReadError> {
+    offset
+        .checked_add(len)
+        .and_then(|end| input.get(offset..end))
+        .ok_or(FileReadError::new(FileReadErrorKind::OutOfBounds).at(offset))
+}
+
+/// What went wrong while reading a file, along with where it happened and what was being read
+/// at the time.
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+pub struct FileReadError {
+    pub kind: FileReadErrorKind,
+    /// Byte offset of the problem, from the start of the data being parsed.
+    pub offset: Option<usize>,
+    /// The chunk, or resource usage for index entries, being read.
+    pub chunk_type: Option<BlorbChunkType>,
+    pub resource_id: Option<i32>,
+    /// The lower level error that caused this one.
+    pub cause: Option<Box<FileReadError>>,
 }
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
-pub enum FileReadError {
+pub enum FileReadErrorKind {
     UnexpectedStartingIdentifier(BlorbChunkType),
     /// An invalid length was supplied. Actual and Expected.
     InvalidLength(usize, usize),
+    /// A chunk ID or other four character code that isn't recognised.
     UnknownIdentifier(usize),
+    /// A read ran past the end of the data.
+    OutOfBounds,
     InvalidConversion,
     UnknownFileType,
     UnsupportedOperation,
+    InvalidHeader,
+    InvalidResourceIndex,
+    InvalidResource,
+    InvalidChunk,
+    /// The iFiction metadata isn't well-formed UTF-8 XML.
+    InvalidMetadata,
+    /// A chunk the format requires wasn't found.
+    MissingChunk(BlorbChunkType),
+    InvalidSound,
+    /// AIFF samples must be from 1 to 32 bits.
+    InvalidSampleSize(u16),
+    /// An Ogg packet isn't the Vorbis header expected at that point in the stream.
+    InvalidPacket,
+    /// Z-machine versions run from 1 to 8.
+    UnsupportedVersion(u8),
+    /// A Z-code object's entry or property table couldn't be read.
+    InvalidObject(u16),
+    InvalidDictionary,
+    /// A Glulx function starts with 0xC0 or 0xC1.
+    UnknownFunctionType(u8),
+    /// Glulx locals are 1, 2 or 4 bytes.
+    InvalidLocalsSize(u8),
+    UnknownOpcode(u32),
+    UnknownAddressingMode(u8),
+    /// A decoding table node type that the Glulx spec doesn't define.
+    UnknownNodeType(u8),
+    /// The decoding table's root is a character or string node, which would never end.
+    LeafRoot,
+    /// A string object's type byte isn't 0xE0, 0xE1 or 0xE2.
+    UnknownStringType(u8),
+    /// Direct colour `Plte` chunks are 16 or 32 bits.
+    InvalidColourDepth(u8),
 }
 
-impl Display for FileReadError {
+impl FileReadError {
+    pub fn new(kind: FileReadErrorKind) -> FileReadError {
+        FileReadError {
+            kind,
+            offset: None,
+            chunk_type: None,
+            resource_id: None,
+            cause: None,
+        }
+    }
+
+    pub fn at(self, offset: usize) -> FileReadError {
+        FileReadError {
+            offset: Some(offset),
+            ..self
+        }
+    }
+
+    pub fn in_chunk(self, chunk_type: BlorbChunkType) -> FileReadError {
+        FileReadError {
+            chunk_type: Some(chunk_type),
+            ..self
+        }
+    }
+
+    pub fn for_resource(self, resource_id: i32) -> FileReadError {
+        FileReadError {
+            resource_id: Some(resource_id),
+            ..self
+        }
+    }
+
+    /// Wraps this error as the cause of a new, higher level one.
+    pub fn context(self, kind: FileReadErrorKind) -> FileReadError {
+        FileReadError {
+            cause: Some(Box::new(self)),
+            ..FileReadError::new(kind)
+        }
+    }
+
+    /// Moves this error and its causes `base` bytes further into the file, for errors from parsers
+    /// that were handed a slice that doesn't start at the beginning of the file.
+    pub fn offset_by(self, base: usize) -> FileReadError {
+        FileReadError {
+            offset: self.offset.map(|offset| offset + base),
+            cause: self.cause.map(|cause| Box::new(cause.offset_by(base))),
+            ..self
+        }
+    }
+
+    /// This error followed by each of its causes in turn.
+    pub fn chain(&self) -> impl Iterator<Item = &FileReadError> {
+        std::iter::successors(Some(self), |e| e.cause.as_deref())
+    }
+}
+
+impl From<FileReadErrorKind> for FileReadError {
+    fn from(kind: FileReadErrorKind) -> Self {
+        FileReadError::new(kind)
+    }
+}
+
+impl Display for FileReadErrorKind {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            FileReadError::UnexpectedStartingIdentifier(expected) => {
+            FileReadErrorKind::UnexpectedStartingIdentifier(expected) => {
                 write!(f, "Unexpected starting identifier. Expected {:?}", expected)
             }
-            FileReadError::InvalidLength(actual, expected) => {
+            FileReadErrorKind::InvalidLength(actual, expected) => {
                 write!(
                     f,
                     "An invalid length was supplied. Actual length: {}, expected length: {}",
                     actual, expected
                 )
             }
-            FileReadError::UnknownIdentifier(id) => {
-                write!(f, "An unknown identifier was supplied: {}", id)
+            FileReadErrorKind::UnknownIdentifier(id) => {
+                write!(
+                    f,
+                    "An unknown identifier was supplied: {} ({:?})",
+                    id,
+                    String::from_utf8_lossy(&(*id as u32).to_be_bytes())
+                )
+            }
+            FileReadErrorKind::OutOfBounds => {
+                write!(f, "Tried to read past the end of the data")
             }
-            FileReadError::InvalidConversion => {
+            FileReadErrorKind::InvalidConversion => {
                 write!(f, "An invalid conversion was attempted")
             }
-            FileReadError::UnknownFileType => {
+            FileReadErrorKind::UnknownFileType => {
                 write!(f, "A file was supplied but did not fit a known file type")
             }
-            FileReadError::UnsupportedOperation => {
+            FileReadErrorKind::UnsupportedOperation => {
                 write!(f, "An unsupported chunk type tried to be read")
             }
+            FileReadErrorKind::InvalidHeader => {
+                write!(f, "The game header could not be read")
+            }
+            FileReadErrorKind::InvalidResourceIndex => {
+                write!(f, "The resource index could not be read")
+            }
+            FileReadErrorKind::InvalidResource => {
+                write!(f, "A resource could not be read")
+            }
+            FileReadErrorKind::InvalidChunk => {
+                write!(f, "A top-level chunk could not be read")
+            }
+            FileReadErrorKind::InvalidMetadata => {
+                write!(f, "The iFiction metadata is not valid XML")
+            }
+            FileReadErrorKind::MissingChunk(chunk_type) => {
+                write!(f, "A required {:?} chunk is missing", chunk_type)
+            }
+            FileReadErrorKind::InvalidSound => {
+                write!(f, "The sound could not be read")
+            }
+            FileReadErrorKind::InvalidSampleSize(bits) => {
+                write!(f, "Samples of {} bits are not supported", bits)
+            }
+            FileReadErrorKind::InvalidPacket => {
+                write!(f, "An Ogg packet is not the expected Vorbis header")
+            }
+            FileReadErrorKind::UnsupportedVersion(version) => {
+                write!(f, "Z-machine version {} is not supported", version)
+            }
+            FileReadErrorKind::InvalidObject(number) => {
+                write!(f, "Object {} could not be read", number)
+            }
+            FileReadErrorKind::InvalidDictionary => {
+                write!(f, "The dictionary could not be read")
+            }
+            FileReadErrorKind::UnknownFunctionType(function_type) => {
+                write!(f, "Unknown function type 0x{:02X}", function_type)
+            }
+            FileReadErrorKind::InvalidLocalsSize(size) => {
+                write!(f, "Locals of {} bytes are not supported", size)
+            }
+            FileReadErrorKind::UnknownOpcode(opcode) => {
+                write!(f, "Unknown opcode 0x{:X}", opcode)
+            }
+            FileReadErrorKind::UnknownAddressingMode(mode) => {
+                write!(f, "Unknown operand addressing mode 0x{:X}", mode)
+            }
+            FileReadErrorKind::UnknownNodeType(node_type) => {
+                write!(f, "Unknown decoding table node type 0x{:02X}", node_type)
+            }
+            FileReadErrorKind::LeafRoot => {
+                write!(f, "The decoding table's root is not a branch")
+            }
+            FileReadErrorKind::UnknownStringType(string_type) => {
+                write!(f, "Unknown string type 0x{:02X}", string_type)
+            }
+            FileReadErrorKind::InvalidColourDepth(bits) => {
+                write!(f, "Direct colour depths of {} bits are not supported", bits)
+            }
+        }
+    }
+}
+
+/// Shows only this error; walk `chain` or `source` for the causes.
+impl Display for FileReadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if let Some(offset) = self.offset {
+            write!(f, " at offset {offset} (0x{offset:X})")?;
         }
+        if let Some(chunk_type) = self.chunk_type {
+            write!(f, " in {chunk_type:?} chunk")?;
+        }
+        if let Some(resource_id) = self.resource_id {
+            write!(f, " for resource {resource_id}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for FileReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.cause
+            .as_deref()
+            .map(|cause| cause as &(dyn std::error::Error + 'static))
     }
 }
 
-impl std::error::Error for FileReadError {}
+#[cfg(test)]
+mod tests {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use super::fuzz_exercise::exercise;
+
+    #[test]
+    fn fuzz_corpus_does_not_panic() {
+        let corpus = concat!(env!("CARGO_MANIFEST_DIR"), "/fuzz/corpus/parse_game");
+        let mut paths: Vec<_> = std::fs::read_dir(corpus)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        paths.sort();
+        assert!(!paths.is_empty());
+
+        let panicked: Vec<_> = paths
+            .iter()
+            .filter(|path| {
+                let data = std::fs::read(path).unwrap();
+                catch_unwind(AssertUnwindSafe(|| exercise(&data))).is_err()
+            })
+            .collect();
+        assert!(panicked.is_empty(), "panicked on {panicked:?}");
+    }
+}
@@ -0,0 +1,260 @@
+use std::fmt::{Display, Formatter};
+
+use super::blorb_chunk_types::BlorbChunkType;
+use super::FileReadErrorKind::{InvalidPacket, UnexpectedStartingIdentifier};
+use super::{read_be_u32, read_bytes, read_le_u32, read_le_u64, read_u8, FileReadError};
+
+/// An Ogg Vorbis sound, read from the data of an `OGGV` chunk. Only the first logical stream is
+/// described, as a Blorb sound holds just one.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct VorbisSound<'a> {
+    pub pages: Vec<OggPage<'a>>,
+    pub identification: VorbisIdentification,
+    pub comments: VorbisComments,
+}
+
+/// One page of the Ogg container, with its header fields.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct OggPage<'a> {
+    /// Where the page starts in the chunk data.
+    pub offset: usize,
+    pub version: u8,
+    /// Continued packet (1), first page (2) and last page (4) flags.
+    pub header_type: u8,
+    /// For Vorbis, the sample frame the last packet that finishes on this page ends at.
+    pub granule_position: u64,
+    pub serial: u32,
+    pub sequence: u32,
+    pub checksum: u32,
+    /// The lacing values: how the page's data divides into packets.
+    pub segment_table: &'a [u8],
+    pub data: &'a [u8],
+}
+
+/// The first Vorbis header packet, describing the audio.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct VorbisIdentification {
+    pub version: u32,
+    pub channels: u8,
+    pub sample_rate: u32,
+    /// Bitrates in bits per second, where zero or negative means not set.
+    pub bitrate_maximum: i32,
+    pub bitrate_nominal: i32,
+    pub bitrate_minimum: i32,
+    pub blocksize_0: u16,
+    pub blocksize_1: u16,
+}
+
+/// The second Vorbis header packet: the encoder's vendor string and `NAME=value` comments.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct VorbisComments {
+    pub vendor: String,
+    pub comments: Vec<(String, String)>,
+}
+
+const PAGE_HEADER_SIZE: usize = 27;
+/// A granule position of all ones means no packet finishes on the page.
+const NO_GRANULE_POSITION: u64 = u64::MAX;
+const IDENTIFICATION_PACKET: u8 = 1;
+const COMMENT_PACKET: u8 = 3;
+
+impl<'a> TryFrom<&'a [u8]> for VorbisSound<'a> {
+    type Error = FileReadError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let mut pages = Vec::new();
+        let mut offset = 0;
+        while offset < value.len() {
+            let page = OggPage::at(value, offset)?;
+            offset += PAGE_HEADER_SIZE + page.segment_table.len() + page.data.len();
+            pages.push(page);
+        }
+        let serial = pages
+            .first()
+            .ok_or(
+                FileReadError::new(UnexpectedStartingIdentifier(BlorbChunkType::OGG_PAGE)).at(0),
+            )?
+            .serial;
+        pages.retain(|page| page.serial == serial);
+
+        // Offsets inside a packet don't map back onto the chunk, as packets can span pages, so
+        // header errors are reported at the page the packet starts on.
+        let mut packets = Packets::new(&pages);
+        let (packet_offset, packet) = packets.next().unwrap_or_default();
+        let identification = VorbisIdentification::try_from(packet.as_slice())
+            .map_err(|_| FileReadError::new(InvalidPacket).at(packet_offset))?;
+        let (packet_offset, packet) = packets.next().unwrap_or_default();
+        let comments = VorbisComments::try_from(packet.as_slice())
+            .map_err(|_| FileReadError::new(InvalidPacket).at(packet_offset))?;
+
+        Ok(VorbisSound {
+            pages,
+            identification,
+            comments,
+        })
+    }
+}
+
+impl<'a> OggPage<'a> {
+    /// Reads the page starting at `offset` in `data`.
+    pub fn at(data: &'a [u8], offset: usize) -> Result<OggPage<'a>, FileReadError> {
+        if read_be_u32(data, offset)? != BlorbChunkType::OGG_PAGE as u32 {
+            return Err(
+                FileReadError::new(UnexpectedStartingIdentifier(BlorbChunkType::OGG_PAGE))
+                    .at(offset),
+            );
+        }
+        let segment_count = read_u8(data, offset + 26)? as usize;
+        let segment_table = read_bytes(data, offset + PAGE_HEADER_SIZE, segment_count)?;
+        let data_len = segment_table.iter().map(|&lacing| lacing as usize).sum();
+        Ok(OggPage {
+            offset,
+            version: read_u8(data, offset + 4)?,
+            header_type: read_u8(data, offset + 5)?,
+            granule_position: read_le_u64(data, offset + 6)?,
+            serial: read_le_u32(data, offset + 14)?,
+            sequence: read_le_u32(data, offset + 18)?,
+            checksum: read_le_u32(data, offset + 22)?,
+            segment_table,
+            data: read_bytes(data, offset + PAGE_HEADER_SIZE + segment_count, data_len)?,
+        })
+    }
+}
+
+/// Joins pages' segments back into packets, along with the offset of the page each starts on.
+/// A lacing value below 255 ends a packet, so packets can span pages.
+struct Packets<'p, 'a> {
+    pages: &'p [OggPage<'a>],
+    page: usize,
+    segment: usize,
+}
+
+impl<'p, 'a> Packets<'p, 'a> {
+    fn new(pages: &'p [OggPage<'a>]) -> Self {
+        Packets {
+            pages,
+            page: 0,
+            segment: 0,
+        }
+    }
+}
+
+impl Iterator for Packets<'_, '_> {
+    type Item = (usize, Vec<u8>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.pages.get(self.page)?.offset;
+        let mut packet = Vec::new();
+        while let Some(page) = self.pages.get(self.page) {
+            let data_start: usize = page.segment_table[..self.segment]
+                .iter()
+                .map(|&lacing| lacing as usize)
+                .sum();
+            let Some(&lacing) = page.segment_table.get(self.segment) else {
+                self.page += 1;
+                self.segment = 0;
+                continue;
+            };
+            packet.extend_from_slice(&page.data[data_start..data_start + lacing as usize]);
+            self.segment += 1;
+            if lacing < 255 {
+                return Some((start, packet));
+            }
+        }
+        (!packet.is_empty()).then_some((start, packet))
+    }
+}
+
+/// Checks a Vorbis header packet's type byte and `vorbis` signature.
+fn check_packet_header(packet: &[u8], packet_type: u8) -> Result<(), FileReadError> {
+    if read_u8(packet, 0)? != packet_type || read_bytes(packet, 1, 6)? != b"vorbis" {
+        return Err(FileReadError::new(InvalidPacket).at(0));
+    }
+    Ok(())
+}
+
+impl TryFrom<&[u8]> for VorbisIdentification {
+    type Error = FileReadError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        check_packet_header(value, IDENTIFICATION_PACKET)?;
+        let blocksizes = read_u8(value, 28)?;
+        Ok(VorbisIdentification {
+            version: read_le_u32(value, 7)?,
+            channels: read_u8(value, 11)?,
+            sample_rate: read_le_u32(value, 12)?,
+            bitrate_maximum: read_le_u32(value, 16)? as i32,
+            bitrate_nominal: read_le_u32(value, 20)? as i32,
+            bitrate_minimum: read_le_u32(value, 24)? as i32,
+            blocksize_0: 1 << (blocksizes & 0x0F),
+            blocksize_1: 1 << (blocksizes >> 4),
+        })
+    }
+}
+
+impl TryFrom<&[u8]> for VorbisComments {
+    type Error = FileReadError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        check_packet_header(value, COMMENT_PACKET)?;
+        let vendor_len = read_le_u32(value, 7)? as usize;
+        let vendor = String::from_utf8_lossy(read_bytes(value, 11, vendor_len)?).into_owned();
+        let mut offset = 11 + vendor_len;
+        let count = read_le_u32(value, offset)?;
+        offset += 4;
+        let mut comments = Vec::new();
+        for _ in 0..count {
+            let len = read_le_u32(value, offset)? as usize;
+            let comment = String::from_utf8_lossy(read_bytes(value, offset + 4, len)?);
+            let (name, text) = comment.split_once('=').unwrap_or((&comment, ""));
+            comments.push((name.to_string(), text.to_string()));
+            offset += 4 + len;
+        }
+        Ok(VorbisComments { vendor, comments })
+    }
+}
+
+impl VorbisSound<'_> {
+    /// The number of sample frames, from the granule position of the last page that has one.
+    pub fn sample_frames(&self) -> u64 {
+        self.pages
+            .iter()
+            .rev()
+            .map(|page| page.granule_position)
+            .find(|&granule| granule != NO_GRANULE_POSITION)
+            .unwrap_or(0)
+    }
+
+    /// The length of the sound in seconds, or zero if the sample rate makes no sense.
+    pub fn duration(&self) -> f64 {
+        if self.identification.sample_rate > 0 {
+            self.sample_frames() as f64 / self.identification.sample_rate as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Display for VorbisSound<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let id = &self.identification;
+        writeln!(f, "Format: Ogg Vorbis")?;
+        writeln!(f, "Channels: {}", id.channels)?;
+        writeln!(f, "Sample rate: {} Hz", id.sample_rate)?;
+        writeln!(
+            f,
+            "Sample frames: {} ({:.2} seconds)",
+            self.sample_frames(),
+            self.duration()
+        )?;
+        if id.bitrate_nominal > 0 {
+            writeln!(f, "Nominal bitrate: {} bits/s", id.bitrate_nominal)?;
+        }
+        writeln!(f, "Ogg pages: {}", self.pages.len())?;
+        writeln!(f, "Vendor: {}", self.comments.vendor)?;
+        for (name, text) in &self.comments.comments {
+            writeln!(f, "{name}: {text}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,215 @@
+use std::fmt::{Display, Formatter};
+
+use super::blorb_chunk_types::fourcc_to_string;
+use super::FileReadErrorKind::{InvalidLength, UnknownIdentifier};
+use super::{read_be_u16, read_be_u32, read_bytes, read_u8, FileReadError};
+
+/// A ProTracker style MOD module, as stored in a `MOD ` chunk. Only the 31 sample format, which
+/// has a signature at offset 1080, is recognised.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct TrackerModule<'a> {
+    pub title: String,
+    pub signature: u32,
+    pub channels: usize,
+    pub samples: Vec<Sample<'a>>,
+    /// How many entries of `order` are played.
+    pub song_length: usize,
+    /// The pattern played at each position of the song. All 128 entries are kept, as unplayed
+    /// ones still decide how many patterns the file holds.
+    pub order: Vec<u8>,
+    pub patterns: Vec<Pattern>,
+}
+
+/// One of the module's instruments, with its sample data. Lengths and loop points are in bytes,
+/// though the file stores them in 16-bit words. A `SONG`'s samples have no data, as it's kept in
+/// the AIFF sound resources their names refer to.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Sample<'a> {
+    pub name: String,
+    pub length: usize,
+    /// Signed fine tuning in eighths of a semitone, from -8 to 7.
+    pub finetune: i8,
+    /// From 0 to 64.
+    pub volume: u8,
+    pub loop_start: usize,
+    /// A loop of 2 bytes or fewer means the sample doesn't loop.
+    pub loop_length: usize,
+    /// Signed 8-bit PCM.
+    pub data: &'a [u8],
+}
+
+/// 64 rows, each holding a note for every channel.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Pattern {
+    pub rows: Vec<Vec<Note>>,
+}
+
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Note {
+    /// 1-based, with 0 meaning keep the channel's current sample.
+    pub sample: u8,
+    /// The Amiga period, where 0 means no new note.
+    pub period: u16,
+    pub effect: u8,
+    pub parameter: u8,
+}
+
+const TITLE_LEN: usize = 20;
+const SAMPLE_COUNT: usize = 31;
+const SAMPLE_HEADER_SIZE: usize = 30;
+const SONG_LENGTH_OFFSET: usize = 950;
+const ORDER_OFFSET: usize = 952;
+const ORDER_LEN: usize = 128;
+const SIGNATURE_OFFSET: usize = 1080;
+const PATTERN_OFFSET: usize = 1084;
+pub const ROWS_PER_PATTERN: usize = 64;
+const NOTE_SIZE: usize = 4;
+
+/// The number of channels a module signature declares, or `None` if it isn't one.
+fn channels_for_signature(signature: [u8; 4]) -> Option<usize> {
+    match &signature {
+        b"M.K." | b"M!K!" | b"FLT4" | b"4CHN" => Some(4),
+        b"FLT8" => Some(8),
+        [n, b'C', b'H', b'N'] if n.is_ascii_digit() => Some((n - b'0') as usize),
+        [a, b, b'C', b'H'] if a.is_ascii_digit() && b.is_ascii_digit() => {
+            Some(((a - b'0') * 10 + (b - b'0')) as usize)
+        }
+        _ => None,
+    }
+    .filter(|&channels| channels > 0)
+}
+
+/// A fixed-size, zero-padded text field.
+fn read_name(input: &[u8], offset: usize, len: usize) -> Result<String, FileReadError> {
+    let bytes = read_bytes(input, offset, len)?;
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(len);
+    Ok(String::from_utf8_lossy(&bytes[..end])
+        .trim_end()
+        .to_string())
+}
+
+impl<'a> TryFrom<&'a [u8]> for TrackerModule<'a> {
+    type Error = FileReadError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        TrackerModule::read(value, true)
+    }
+}
+
+impl<'a> TrackerModule<'a> {
+    /// Reads a `SONG` chunk: a MOD whose sample data has been left out.
+    pub fn without_sample_data(value: &'a [u8]) -> Result<Self, FileReadError> {
+        TrackerModule::read(value, false)
+    }
+
+    fn read(value: &'a [u8], with_sample_data: bool) -> Result<Self, FileReadError> {
+        let signature = read_be_u32(value, SIGNATURE_OFFSET)?;
+        let channels = channels_for_signature(signature.to_be_bytes()).ok_or(
+            FileReadError::new(UnknownIdentifier(signature as usize)).at(SIGNATURE_OFFSET),
+        )?;
+        let song_length = read_u8(value, SONG_LENGTH_OFFSET)? as usize;
+        if song_length == 0 || song_length > ORDER_LEN {
+            return Err(
+                FileReadError::new(InvalidLength(song_length, ORDER_LEN)).at(SONG_LENGTH_OFFSET)
+            );
+        }
+        let order = read_bytes(value, ORDER_OFFSET, ORDER_LEN)?.to_vec();
+        let pattern_count = *order.iter().max().unwrap() as usize + 1;
+
+        let pattern_size = ROWS_PER_PATTERN * channels * NOTE_SIZE;
+        let patterns = (0..pattern_count)
+            .map(|i| Pattern::at(value, PATTERN_OFFSET + i * pattern_size, channels))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut data_offset = PATTERN_OFFSET + pattern_count * pattern_size;
+        let mut samples = Vec::with_capacity(SAMPLE_COUNT);
+        for i in 0..SAMPLE_COUNT {
+            let offset = TITLE_LEN + i * SAMPLE_HEADER_SIZE;
+            let length = read_be_u16(value, offset + 22)? as usize * 2;
+            samples.push(Sample {
+                name: read_name(value, offset, 22)?,
+                length,
+                // The finetune is a signed nibble.
+                finetune: ((read_u8(value, offset + 24)? << 4) as i8) >> 4,
+                volume: read_u8(value, offset + 25)?.min(64),
+                loop_start: read_be_u16(value, offset + 26)? as usize * 2,
+                loop_length: read_be_u16(value, offset + 28)? as usize * 2,
+                data: if with_sample_data {
+                    read_bytes(value, data_offset, length)?
+                } else {
+                    &[]
+                },
+            });
+            data_offset += length;
+        }
+
+        Ok(TrackerModule {
+            title: read_name(value, 0, TITLE_LEN)?,
+            signature,
+            channels,
+            samples,
+            song_length,
+            order,
+            patterns,
+        })
+    }
+}
+
+impl Pattern {
+    fn at(input: &[u8], offset: usize, channels: usize) -> Result<Pattern, FileReadError> {
+        let data = read_bytes(input, offset, ROWS_PER_PATTERN * channels * NOTE_SIZE)?;
+        let rows = data
+            .chunks_exact(channels * NOTE_SIZE)
+            .map(|row| {
+                row.chunks_exact(NOTE_SIZE)
+                    .map(|n| Note {
+                        sample: (n[0] & 0xF0) | (n[2] >> 4),
+                        period: u16::from_be_bytes([n[0] & 0x0F, n[1]]),
+                        effect: n[2] & 0x0F,
+                        parameter: n[3],
+                    })
+                    .collect()
+            })
+            .collect();
+        Ok(Pattern { rows })
+    }
+}
+
+impl Sample<'_> {
+    /// Whether the sample repeats its loop once played through, rather than stopping.
+    pub fn loops(&self) -> bool {
+        self.loop_length > 2 && self.loop_start + self.loop_length <= self.data.len()
+    }
+
+    /// The sound resource a `SONG` sample named `SNDnnnn` takes its data from.
+    pub fn sound_id(&self) -> Option<i32> {
+        let number = self.name.strip_prefix("SND")?;
+        if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        number.parse().ok()
+    }
+}
+
+impl TrackerModule<'_> {
+    /// The patterns in the order the song plays them.
+    pub fn played_order(&self) -> &[u8] {
+        &self.order[..self.song_length]
+    }
+}
+
+impl Display for TrackerModule<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Title: {}", self.title)?;
+        writeln!(
+            f,
+            "Signature: {} ({} channels)",
+            fourcc_to_string(self.signature),
+            self.channels
+        )?;
+        writeln!(f, "Patterns: {}", self.patterns.len())?;
+        let order: Vec<String> = self.played_order().iter().map(|p| p.to_string()).collect();
+        writeln!(f, "Order: {}", order.join(" "))?;
+        Ok(())
+    }
+}
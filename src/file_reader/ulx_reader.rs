@@ -1,11 +1,14 @@
+use std::collections::BTreeMap;
 use std::fmt::{Debug, Display, Formatter};
 use TryInto;
 
 use crate::strings::StringTypes;
 
+use super::decoding_table::DecodingTable;
+use super::disassembler::Disassembler;
 use super::BlorbChunkType::EXEC_GLUL;
-use super::FileReadError::UnexpectedStartingIdentifier;
-use super::{read_be_u32, BlorbChunkType, FileReadError};
+use super::FileReadErrorKind::{InvalidHeader, UnexpectedStartingIdentifier};
+use super::{read_be_u16, read_be_u32, read_bytes, BlorbChunkType, FileReadError};
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub struct UlxReader<'a> {
@@ -18,8 +21,14 @@ impl<'a> TryFrom<&'a [u8]> for UlxReader<'a> {
     type Error = FileReadError;
 
     fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
-        let header: GlulxHeader = value.try_into()?;
-        let debugging_header: GlulxDebuggingHeader = value[HEADER_SIZE..].try_into()?;
+        let header = GlulxHeader::try_from(value).map_err(|e| e.context(InvalidHeader).at(0))?;
+        // The header parsed, so the file is at least HEADER_SIZE bytes long.
+        let debugging_header =
+            GlulxDebuggingHeader::try_from(&value[HEADER_SIZE..]).map_err(|e| {
+                e.offset_by(HEADER_SIZE)
+                    .context(InvalidHeader)
+                    .at(HEADER_SIZE)
+            })?;
         let memory = value;
         Ok(UlxReader {
             header,
@@ -34,27 +43,253 @@ pub(crate) struct ParsedString {
     pub(crate) data: String,
     pub(crate) start_address: usize,
     pub(crate) string_type: StringTypes,
+    pub(crate) source: StringSource,
+}
+
+/// How a string was found, ordered from least to most trustworthy.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, strum_macros::EnumIter)]
+pub enum StringSource {
+    /// A type byte that happened to be followed by something decodable. Mostly noise.
+    Scan,
+    /// Decodes cleanly and sits in ROM, or in a Z-code game's high memory, but nothing was found
+    /// pointing at it. Only a `StringDiscovery::Scan` finds these.
+    Rom,
+    /// Its address appears as an aligned 32-bit word in RAM or in the tables between functions.
+    /// For Z-code, its packed address appears as a word in the tables below high memory.
+    Reference,
+    /// A constant operand of a disassembled instruction points at it. For Z-code, it follows a
+    /// `print` or `print_ret`, or is the operand of a `print_paddr`.
+    Operand,
+    /// An indirect node of the decoding table points at it.
+    DecodingTable,
+    /// An entry of a Z-code game's abbreviations table points at it.
+    Abbreviation,
+}
+
+/// Where `parse_strings` looks for strings.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum StringDiscovery {
+    /// Only where something points.
+    #[default]
+    Referenced,
+    /// Also at every byte of memory, or every packed address of a Z-code game's high memory,
+    /// which finds strings nothing was found pointing at along with a lot of noise.
+    Scan,
+}
+
+impl Display for StringSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StringSource::Scan => f.write_str("Byte scan"),
+            StringSource::Rom => f.write_str("ROM"),
+            StringSource::Reference => f.write_str("Referenced"),
+            StringSource::Operand => f.write_str("Code operand"),
+            StringSource::DecodingTable => f.write_str("Decoding table"),
+            StringSource::Abbreviation => f.write_str("Abbreviation"),
+        }
+    }
 }
 
 impl<'a> UlxReader<'a> {
-    pub(crate) fn parse_strings(&self) -> Vec<ParsedString> {
-        let mut ret = Vec::new();
-        for i in 0..self.memory.len() {
-            if let Ok(string_type) = StringTypes::try_from(self.memory[i]) {
-                let data = string_type.parse(&self.memory[i + 1..]);
-                if !data.is_empty() {
-                    ret.push(ParsedString {
-                        data,
-                        start_address: i,
-                        string_type,
-                    })
+    /// The table used to decode compressed strings, or `None` if the game has no table or it
+    /// could not be read.
+    pub fn decoding_table(&self) -> Option<DecodingTable<'a>> {
+        if self.header.decoding_table_address == 0 {
+            return None;
+        }
+        DecodingTable::new(self.memory, self.header.decoding_table_address).ok()
+    }
+
+    /// Finds the string objects that something points at: a constant operand of the game's code,
+    /// an indirect node of the decoding table, or an aligned word in RAM or in the tables between
+    /// functions. Only strings in ROM are looked for, since Inform puts them all there. With
+    /// `StringDiscovery::Scan`, every byte of memory is also tried as a type byte.
+    pub(crate) fn parse_strings(&self, discovery: StringDiscovery) -> Vec<ParsedString> {
+        let decoding_table = self.decoding_table();
+        let rom_end = (self.header.ram_start as usize).min(self.memory.len());
+        // The text at `address` and whether it decoded without errors or control characters.
+        let decode = |address: usize| {
+            let string_type = StringTypes::try_from(*self.memory.get(address)?).ok()?;
+            let data = string_type.try_parse(&self.memory[address + 1..], decoding_table.as_ref());
+            let is_clean = data.as_deref().is_some_and(|data| {
+                !data
+                    .chars()
+                    .any(|c| c.is_control() && c != '\n' && c != '\t')
+            });
+            Some((string_type, data.unwrap_or_default(), is_clean))
+        };
+        let mut found: BTreeMap<usize, ParsedString> = BTreeMap::new();
+        let mut add = |address: usize, source: StringSource| {
+            let Some((string_type, data, is_clean)) = decode(address) else {
+                return;
+            };
+            if data.is_empty() {
+                return;
+            }
+            let source = match source {
+                StringSource::Scan | StringSource::Rom if is_clean && address < rom_end => {
+                    StringSource::Rom
                 }
+                StringSource::Scan | StringSource::Rom => StringSource::Scan,
+                // Something that only looks like a pointer has to point at a clean string in ROM.
+                _ if !is_clean || address < HEADER_SIZE || address >= rom_end => return,
+                source => source,
+            };
+            let string = found.entry(address).or_insert_with(|| ParsedString {
+                data,
+                start_address: address,
+                string_type,
+                source,
+            });
+            string.source = string.source.max(source);
+        };
+
+        let functions = Disassembler::new(self).disassemble();
+        let mut is_code = vec![false; rom_end];
+        for instruction in functions.iter().flat_map(|f| &f.instructions) {
+            let start = (instruction.address as usize).min(rom_end);
+            let end = (start + instruction.length as usize).min(rom_end);
+            is_code[start..end].fill(true);
+            for address in instruction.constants() {
+                add(address as usize, StringSource::Operand);
+            }
+        }
+        if let Some(table) = &decoding_table {
+            for address in table.references() {
+                add(address as usize, StringSource::DecodingTable);
+            }
+        }
+        // Inform keeps its tables and arrays word aligned, and words made up of opcode and operand
+        // bytes would only be noise, so code is skipped.
+        for (i, word) in self
+            .memory
+            .chunks_exact(4)
+            .enumerate()
+            .skip(HEADER_SIZE / 4)
+        {
+            if !is_code.get(i * 4).copied().unwrap_or_default() {
+                let address = u32::from_be_bytes(word.try_into().unwrap());
+                add(address as usize, StringSource::Reference);
+            }
+        }
+        if discovery == StringDiscovery::Scan {
+            for i in 0..self.memory.len() {
+                add(i, StringSource::Scan);
             }
         }
-        ret
+        found.into_values().collect()
+    }
+}
+
+/// The outcome of checking a story file against the structural rules in the Glulx spec.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct IntegrityReport {
+    pub computed_checksum: u32,
+    pub checks: Vec<IntegrityCheck>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct IntegrityCheck {
+    pub description: String,
+    pub passed: bool,
+}
+
+impl IntegrityReport {
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|c| c.passed)
+    }
+}
+
+impl Display for IntegrityReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for check in &self.checks {
+            let result = if check.passed { "PASS" } else { "FAIL" };
+            writeln!(f, "{result}: {}", check.description)?;
+        }
+        Ok(())
     }
 }
 
+impl<'a> UlxReader<'a> {
+    /// The checksum as the spec defines it: the sum of every 32-bit word before `ext_start`, with
+    /// the header's checksum field counted as zero. Words past the end of the file count as zero.
+    pub fn compute_checksum(&self) -> u32 {
+        let end = (self.header.ext_start as usize).min(self.memory.len());
+        self.memory[..end]
+            .chunks_exact(4)
+            .enumerate()
+            .filter(|&(i, _)| i * 4 != CHECKSUM_OFFSET)
+            .fold(0u32, |sum, (_, word)| {
+                sum.wrapping_add(u32::from_be_bytes(word.try_into().unwrap()))
+            })
+    }
+
+    /// Checks the checksum, the alignment and order of the memory boundaries, and that the file
+    /// is as long as the header says it should be.
+    pub fn integrity_report(&self) -> IntegrityReport {
+        let header = &self.header;
+        let computed_checksum = self.compute_checksum();
+        let mut checks = Vec::new();
+        let mut check = |passed: bool, description: String| {
+            checks.push(IntegrityCheck {
+                description,
+                passed,
+            })
+        };
+
+        check(
+            computed_checksum == header.checksum,
+            format!(
+                "Checksum: header says 0x{:08X}, computed 0x{computed_checksum:08X}",
+                header.checksum
+            ),
+        );
+        for (name, value) in [
+            ("ram_start", header.ram_start),
+            ("ext_start", header.ext_start),
+            ("end_mem", header.end_mem),
+        ] {
+            check(
+                value % MEMORY_ALIGNMENT == 0,
+                format!("{name} ({value}) is a multiple of {MEMORY_ALIGNMENT}"),
+            );
+        }
+        check(
+            header.ram_start >= MEMORY_ALIGNMENT,
+            format!(
+                "ROM ({} bytes) is at least {MEMORY_ALIGNMENT} bytes",
+                header.ram_start
+            ),
+        );
+        check(
+            header.ram_start <= header.ext_start && header.ext_start <= header.end_mem,
+            format!(
+                "ram_start ({}) <= ext_start ({}) <= end_mem ({})",
+                header.ram_start, header.ext_start, header.end_mem
+            ),
+        );
+        check(
+            self.memory.len() == header.ext_start as usize,
+            format!(
+                "File length ({}) matches ext_start ({})",
+                self.memory.len(),
+                header.ext_start
+            ),
+        );
+
+        IntegrityReport {
+            computed_checksum,
+            checks,
+        }
+    }
+}
+
+/// Where the checksum sits in the header, in bytes.
+const CHECKSUM_OFFSET: usize = 32;
+
+/// `ram_start`, `ext_start` and `end_mem` must all fall on a boundary of this many bytes.
+const MEMORY_ALIGNMENT: u32 = 256;
+
 // The size of the GlulxHeader in bytes
 static HEADER_SIZE: usize = 36;
 
@@ -106,25 +341,25 @@ impl Display for GlulxHeader {
     }
 }
 
-static GLUL_AS_NUM: u32 = 1198290284;
+pub(crate) const GLUL_AS_NUM: u32 = 1198290284;
 impl TryFrom<&[u8]> for GlulxHeader {
     type Error = FileReadError;
 
     fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
-        let magic_num = read_be_u32(&bytes[..4]);
+        let magic_num = read_be_u32(bytes, 0)?;
         if magic_num != GLUL_AS_NUM {
-            return Err(UnexpectedStartingIdentifier(EXEC_GLUL));
+            return Err(FileReadError::new(UnexpectedStartingIdentifier(EXEC_GLUL)).at(0));
         }
         Ok(GlulxHeader {
             magic_num,
-            version: read_be_u32(&bytes[4..8]),
-            ram_start: read_be_u32(&bytes[8..12]),
-            ext_start: read_be_u32(&bytes[12..16]),
-            end_mem: read_be_u32(&bytes[16..20]),
-            stack_size: read_be_u32(&bytes[20..24]),
-            start_function_address: read_be_u32(&bytes[24..28]),
-            decoding_table_address: read_be_u32(&bytes[28..32]),
-            checksum: read_be_u32(&bytes[32..36]),
+            version: read_be_u32(bytes, 4)?,
+            ram_start: read_be_u32(bytes, 8)?,
+            ext_start: read_be_u32(bytes, 12)?,
+            end_mem: read_be_u32(bytes, 16)?,
+            stack_size: read_be_u32(bytes, 20)?,
+            start_function_address: read_be_u32(bytes, 24)?,
+            decoding_table_address: read_be_u32(bytes, 28)?,
+            checksum: read_be_u32(bytes, 32)?,
         })
     }
 }
@@ -166,17 +401,19 @@ impl TryFrom<&[u8]> for GlulxDebuggingHeader {
     type Error = FileReadError;
 
     fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
-        let id = read_be_u32(&value[..4]);
+        let id = read_be_u32(value, 0)?;
         if id != INFO_AS_NUM {
-            return Err(UnexpectedStartingIdentifier(BlorbChunkType::INFO));
+            return Err(
+                FileReadError::new(UnexpectedStartingIdentifier(BlorbChunkType::INFO)).at(0),
+            );
         }
         Ok(GlulxDebuggingHeader {
             id,
-            memory_layout: read_be_u32(&value[4..8]),
-            inform_version: read_be_u32(&value[8..12]),
-            glulx_compiler_version: read_be_u32(&value[12..16]),
-            game_version: u16::from_be_bytes(value[16..18].try_into().unwrap()),
-            game_serial_number: value[18..24].try_into().unwrap(),
+            memory_layout: read_be_u32(value, 4)?,
+            inform_version: read_be_u32(value, 8)?,
+            glulx_compiler_version: read_be_u32(value, 12)?,
+            game_version: read_be_u16(value, 16)?,
+            game_serial_number: read_bytes(value, 18, 6)?.try_into().unwrap(),
         })
     }
 }
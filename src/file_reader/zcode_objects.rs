@@ -0,0 +1,297 @@
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+
+use super::zcode_reader::ZcodeReader;
+use super::zscii::ZsciiDecoder;
+use super::FileReadErrorKind::InvalidObject;
+use super::{read_be_u16, read_bytes, read_u8, FileReadError};
+
+/// The objects of a Z-code game's world model, from the object table the header points at.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ObjectTable {
+    /// The value of each property for objects that don't have it, from property 1 up.
+    pub property_defaults: Vec<u16>,
+    /// Object 1 onwards. Object 0 is "nothing" and has no entry.
+    pub objects: Vec<ZObject>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ZObject {
+    pub number: u16,
+    /// The attributes that are set, in ascending order.
+    pub attributes: Vec<u8>,
+    pub parent: u16,
+    pub sibling: u16,
+    pub child: u16,
+    pub property_table: usize,
+    pub short_name: String,
+    /// In the order they're stored, which is descending by number.
+    pub properties: Vec<Property>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Property {
+    pub number: u8,
+    /// Where the data starts, after the size byte or bytes.
+    pub address: usize,
+    pub data: Vec<u8>,
+}
+
+/// How the object table is laid out in the two families of versions.
+struct Layout {
+    defaults: usize,
+    entry_size: usize,
+    attribute_bytes: usize,
+    max_objects: usize,
+}
+
+const VERSION_1_TO_3: Layout = Layout {
+    defaults: 31,
+    entry_size: 9,
+    attribute_bytes: 4,
+    max_objects: 255,
+};
+const VERSION_4_ON: Layout = Layout {
+    defaults: 63,
+    entry_size: 14,
+    attribute_bytes: 6,
+    max_objects: 65535,
+};
+
+impl TryFrom<&ZcodeReader<'_>> for ObjectTable {
+    type Error = FileReadError;
+
+    /// The table doesn't say how many objects there are, but the property tables conventionally
+    /// follow the entries, so entries are read until they would run into the first of those.
+    fn try_from(reader: &ZcodeReader<'_>) -> Result<Self, Self::Error> {
+        let memory = reader.memory;
+        let version = reader.header.version;
+        let layout = if version <= 3 {
+            VERSION_1_TO_3
+        } else {
+            VERSION_4_ON
+        };
+        let decoder = ZsciiDecoder::new(reader)?;
+        let table = reader.header.object_table as usize;
+        let property_defaults = (0..layout.defaults)
+            .map(|i| read_be_u16(memory, table + i * 2))
+            .collect::<Result<_, _>>()?;
+
+        let first_entry = table + layout.defaults * 2;
+        let mut first_property_table = memory.len();
+        let mut objects = Vec::new();
+        for number in 1..=layout.max_objects as u16 {
+            let entry = first_entry + (number as usize - 1) * layout.entry_size;
+            if entry + layout.entry_size > first_property_table {
+                break;
+            }
+            let object = read_object(memory, &decoder, &layout, version, number, entry)
+                .map_err(|e| e.context(InvalidObject(number)).at(entry))?;
+            first_property_table = first_property_table.min(object.property_table);
+            objects.push(object);
+        }
+        Ok(ObjectTable {
+            property_defaults,
+            objects,
+        })
+    }
+}
+
+fn read_object(
+    memory: &[u8],
+    decoder: &ZsciiDecoder,
+    layout: &Layout,
+    version: u8,
+    number: u16,
+    entry: usize,
+) -> Result<ZObject, FileReadError> {
+    let attribute_bytes = read_bytes(memory, entry, layout.attribute_bytes)?;
+    let attributes = (0..layout.attribute_bytes as u8 * 8)
+        .filter(|&a| attribute_bytes[a as usize / 8] & (0x80 >> (a % 8)) != 0)
+        .collect();
+    let links = entry + layout.attribute_bytes;
+    let (parent, sibling, child, property_table) = if version <= 3 {
+        (
+            read_u8(memory, links)? as u16,
+            read_u8(memory, links + 1)? as u16,
+            read_u8(memory, links + 2)? as u16,
+            read_be_u16(memory, links + 3)? as usize,
+        )
+    } else {
+        (
+            read_be_u16(memory, links)?,
+            read_be_u16(memory, links + 2)?,
+            read_be_u16(memory, links + 4)?,
+            read_be_u16(memory, links + 6)? as usize,
+        )
+    };
+
+    let name_words = read_u8(memory, property_table)? as usize;
+    let short_name = match name_words {
+        0 => String::new(),
+        _ => decoder.decode(property_table + 1)?.0,
+    };
+    let mut properties = Vec::new();
+    let mut offset = property_table + 1 + name_words * 2;
+    loop {
+        let size = read_u8(memory, offset)?;
+        if size == 0 {
+            break;
+        }
+        let (number, length, size_bytes) = if version <= 3 {
+            (size & 31, (size >> 5) as usize + 1, 1)
+        } else if size & 0x80 != 0 {
+            // A length of 0 in the second byte means 64.
+            let length = match read_u8(memory, offset + 1)? & 63 {
+                0 => 64,
+                length => length as usize,
+            };
+            (size & 63, length, 2)
+        } else {
+            (size & 63, if size & 0x40 != 0 { 2 } else { 1 }, 1)
+        };
+        let address = offset + size_bytes;
+        properties.push(Property {
+            number,
+            address,
+            data: read_bytes(memory, address, length)?.to_vec(),
+        });
+        offset = address + length;
+    }
+
+    Ok(ZObject {
+        number,
+        attributes,
+        parent,
+        sibling,
+        child,
+        property_table,
+        short_name,
+        properties,
+    })
+}
+
+impl ObjectTable {
+    /// The object numbered `number`, or `None` for 0 and numbers past the end of the table.
+    pub fn get(&self, number: u16) -> Option<&ZObject> {
+        self.objects.get((number as usize).checked_sub(1)?)
+    }
+
+    /// The objects with no parent, which the rest of the tree hangs from.
+    pub fn roots(&self) -> Vec<u16> {
+        self.objects
+            .iter()
+            .filter(|object| object.parent == 0)
+            .map(|object| object.number)
+            .collect()
+    }
+
+    /// The children of `number`, following its child and then each child's sibling. Stops at an
+    /// object seen before, so a corrupt table that loops can't hang the caller.
+    pub fn children(&self, number: u16) -> Vec<u16> {
+        let mut seen = HashSet::new();
+        let mut children = Vec::new();
+        let mut next = self.get(number).map_or(0, |object| object.child);
+        while let Some(child) = self.get(next) {
+            if !seen.insert(next) {
+                break;
+            }
+            children.push(next);
+            next = child.sibling;
+        }
+        children
+    }
+
+    /// Objects whose parent doesn't list them among its children, which the tree won't show
+    /// where their parent field says they are.
+    pub fn misplaced(&self) -> Vec<u16> {
+        self.objects
+            .iter()
+            .filter(|object| {
+                object.parent != 0 && !self.children(object.parent).contains(&object.number)
+            })
+            .map(|object| object.number)
+            .collect()
+    }
+}
+
+impl Display for ZObject {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.short_name.as_str() {
+            "" => write!(f, "{} (no name)", self.number),
+            name => write!(f, "{} \"{name}\"", self.number),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OBJECT_TABLE: usize = 0x40;
+    const FIRST_ENTRY: usize = OBJECT_TABLE + 63 * 2;
+
+    /// A version 4 story with a box holding a key and a coin, and a lamp whose parent is the key
+    /// but which isn't among the key's children.
+    fn story() -> Vec<u8> {
+        let mut memory = vec![0; 0x180];
+        let mut put = |address: usize, bytes: &[u8]| {
+            memory[address..address + bytes.len()].copy_from_slice(bytes);
+        };
+        put(0, &[4]);
+        put(0x0A, &(OBJECT_TABLE as u16).to_be_bytes());
+        // Attributes, then parent, sibling, child and property table.
+        for (i, entry) in [
+            [0x80, 0, 0, 0, 0, 0x01, 0, 0, 0, 0, 0, 2, 0x01, 0x00],
+            [0, 0, 0, 0, 0, 0, 0, 1, 0, 3, 0, 0, 0x01, 0x50],
+            [0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0x01, 0x60],
+            [0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0x01, 0x70],
+        ]
+        .iter()
+        .enumerate()
+        {
+            put(FIRST_ENTRY + i * 14, entry);
+        }
+        // "box", then property 20 with a two byte size of length 0, meaning 64, then properties 5
+        // and 3 with one byte sizes.
+        put(0x100, &[1, 0x9E, 0x9D, 0x80 | 20, 0x80]);
+        put(0x105 + 64, &[0x40 | 5, 0x12, 0x34, 3, 0x56, 0]);
+        put(0x150, &[0, 0]);
+        put(0x160, &[0, 0]);
+        put(0x170, &[0, 0]);
+        memory
+    }
+
+    #[test]
+    fn reads_tree() {
+        let memory = story();
+        let reader = ZcodeReader::try_from(&memory[..]).unwrap();
+        let table = ObjectTable::try_from(&reader).unwrap();
+        assert_eq!(table.objects.len(), 4);
+        assert_eq!(table.roots(), vec![1]);
+        assert_eq!(table.children(1), vec![2, 3]);
+        assert_eq!(table.children(2), Vec::<u16>::new());
+        assert_eq!(table.misplaced(), vec![4]);
+
+        let key = table.get(2).unwrap();
+        assert_eq!((key.parent, key.sibling, key.child), (1, 3, 0));
+        assert_eq!(table.get(5), None);
+    }
+
+    #[test]
+    fn reads_properties() {
+        let memory = story();
+        let reader = ZcodeReader::try_from(&memory[..]).unwrap();
+        let table = ObjectTable::try_from(&reader).unwrap();
+        let object = table.get(1).unwrap();
+        assert_eq!(object.to_string(), "1 \"box\"");
+        assert_eq!(object.attributes, vec![0, 47]);
+
+        let numbers: Vec<u8> = object.properties.iter().map(|p| p.number).collect();
+        assert_eq!(numbers, vec![20, 5, 3]);
+        assert_eq!(object.properties[0].address, 0x105);
+        assert_eq!(object.properties[0].data.len(), 64);
+        assert_eq!(object.properties[1].data, vec![0x12, 0x34]);
+        assert_eq!(object.properties[2].data, vec![0x56]);
+    }
+}
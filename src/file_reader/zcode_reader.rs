@@ -0,0 +1,325 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+use crate::strings::StringTypes;
+
+use super::ulx_reader::{
+    IntegrityCheck, IntegrityReport, ParsedString, StringDiscovery, StringSource,
+};
+use super::zscii::ZsciiDecoder;
+use super::FileReadErrorKind::{InvalidHeader, UnsupportedVersion};
+use super::{read_be_u16, read_bytes, read_u8, FileReadError};
+
+/// A Z-machine story file, such as a `.z5` or `.z8`, or the `ZCOD` chunk of a `.zblorb`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ZcodeReader<'a> {
+    pub header: ZcodeHeader,
+    pub memory: &'a [u8],
+}
+
+impl<'a> TryFrom<&'a [u8]> for ZcodeReader<'a> {
+    type Error = FileReadError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let header = ZcodeHeader::try_from(value).map_err(|e| e.context(InvalidHeader).at(0))?;
+        Ok(ZcodeReader {
+            header,
+            memory: value,
+        })
+    }
+}
+
+// The size of the ZcodeHeader in bytes
+pub(crate) const ZCODE_HEADER_SIZE: usize = 64;
+
+/// The fields of the 64 byte header that say where things are in memory. Addresses are byte
+/// addresses; the file length is in bytes too, although the header stores it scaled down.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct ZcodeHeader {
+    /// From 1 to 8.
+    pub version: u8,
+    pub release: u16,
+    /// Usually the date the game was compiled, as YYMMDD.
+    pub serial: [u8; 6],
+    pub high_memory: u16,
+    /// Where execution starts, or in version 6 the packed address of the main routine.
+    pub initial_pc: u16,
+    pub dictionary: u16,
+    pub object_table: u16,
+    pub globals: u16,
+    /// Where static memory starts, which is also the length of dynamic memory.
+    pub static_memory: u16,
+    pub abbreviations: u16,
+    /// 0 for the early games that don't give a length.
+    pub file_length: usize,
+    pub checksum: u16,
+    /// Versions 6 and 7 add these, times 8, to packed routine and string addresses.
+    pub routines_offset: u16,
+    pub strings_offset: u16,
+    /// A replacement for the three alphabets text is encoded with, from version 5. 0 if the
+    /// standard alphabets are used.
+    pub alphabet_table: u16,
+    /// The header extension table, from version 5, or 0 if there isn't one.
+    pub extension_table: u16,
+}
+
+impl ZcodeHeader {
+    /// What packed routine and string addresses are multiplied by to get byte addresses.
+    pub fn packed_address_scale(&self) -> usize {
+        match self.version {
+            1..=3 => 2,
+            4..=7 => 4,
+            _ => 8,
+        }
+    }
+
+    /// What the header's file length is multiplied by to get bytes.
+    pub fn file_length_scale(&self) -> usize {
+        match self.version {
+            1..=3 => 2,
+            4 | 5 => 4,
+            _ => 8,
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for ZcodeHeader {
+    type Error = FileReadError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        read_bytes(bytes, 0, ZCODE_HEADER_SIZE)?;
+        let version = read_u8(bytes, 0)?;
+        if !(1..=8).contains(&version) {
+            return Err(FileReadError::new(UnsupportedVersion(version)).at(0));
+        }
+        let mut header = ZcodeHeader {
+            version,
+            release: read_be_u16(bytes, 0x02)?,
+            serial: read_bytes(bytes, 0x12, 6)?.try_into().unwrap(),
+            high_memory: read_be_u16(bytes, 0x04)?,
+            initial_pc: read_be_u16(bytes, 0x06)?,
+            dictionary: read_be_u16(bytes, 0x08)?,
+            object_table: read_be_u16(bytes, 0x0A)?,
+            globals: read_be_u16(bytes, 0x0C)?,
+            static_memory: read_be_u16(bytes, 0x0E)?,
+            abbreviations: read_be_u16(bytes, 0x18)?,
+            file_length: read_be_u16(bytes, 0x1A)? as usize,
+            checksum: read_be_u16(bytes, 0x1C)?,
+            routines_offset: read_be_u16(bytes, 0x28)?,
+            strings_offset: read_be_u16(bytes, 0x2A)?,
+            alphabet_table: read_be_u16(bytes, 0x34)?,
+            extension_table: read_be_u16(bytes, 0x36)?,
+        };
+        header.file_length *= header.file_length_scale();
+        Ok(header)
+    }
+}
+
+impl Display for ZcodeHeader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_fmt(format_args!(
+            "ZcodeHeader {{ version: {}, release: {}, serial: \"{}\", high_memory: {}, \
+            initial_pc: {}, dictionary: {}, object_table: {}, globals: {}, static_memory: {}, \
+            abbreviations: {}, file_length: {}, checksum: {}, alphabet_table: {}, \
+            extension_table: {} }}",
+            self.version,
+            self.release,
+            String::from_utf8_lossy(&self.serial),
+            self.high_memory,
+            self.initial_pc,
+            self.dictionary,
+            self.object_table,
+            self.globals,
+            self.static_memory,
+            self.abbreviations,
+            self.file_length,
+            self.checksum,
+            self.alphabet_table,
+            self.extension_table
+        ))
+    }
+}
+
+/// The opcodes of `print` and `print_ret`, which are followed by the string they print.
+const INLINE_STRING_OPCODES: [u8; 2] = [0xB2, 0xB3];
+/// The opcode of `print_paddr` with a large constant operand, the packed address of a string.
+const PRINT_PADDR_OPCODE: u8 = 0x8D;
+
+impl ZcodeReader<'_> {
+    /// The byte address of the string at packed address `packed`.
+    pub fn unpack_string_address(&self, packed: u16) -> usize {
+        let address = packed as usize * self.header.packed_address_scale();
+        match self.header.version {
+            6 | 7 => address + self.header.strings_offset as usize * 8,
+            _ => address,
+        }
+    }
+
+    /// Finds the abbreviations, the strings printed by `print`, `print_ret` and `print_paddr`, and
+    /// the strings in high memory whose packed address is in the tables below it. With
+    /// `StringDiscovery::Scan`, every packed-aligned string in high memory is also tried. As with
+    /// Glulx, each candidate is tagged with how much evidence there is that it really is a string.
+    pub(crate) fn parse_strings(&self, discovery: StringDiscovery) -> Vec<ParsedString> {
+        let Ok(decoder) = ZsciiDecoder::new(self) else {
+            return Vec::new();
+        };
+        let high_memory = self.header.high_memory as usize;
+        // Candidates by start address, along with where they end.
+        let mut found: HashMap<usize, (ParsedString, usize)> = HashMap::new();
+        let mut add = |start: usize, source: StringSource| {
+            let Ok((data, end)) = decoder.decode(start) else {
+                return;
+            };
+            if data.trim().is_empty() {
+                return;
+            }
+            let is_clean = !data
+                .chars()
+                .any(|c| c.is_control() && c != '\n' && c != '\t');
+            let source = match source {
+                StringSource::Rom if !is_clean || start < high_memory => StringSource::Scan,
+                // Opcode bytes and table words only look like pointers, so they have to lead to a
+                // clean string in high memory.
+                StringSource::Reference | StringSource::Operand
+                    if !is_clean || start < high_memory =>
+                {
+                    return
+                }
+                source => source,
+            };
+            let entry = found.entry(start).or_insert_with(|| {
+                let string = ParsedString {
+                    data,
+                    start_address: start,
+                    string_type: StringTypes::Zscii,
+                    source,
+                };
+                (string, end)
+            });
+            entry.0.source = entry.0.source.max(source);
+        };
+
+        for index in 0..decoder.abbreviation_count() {
+            if let Ok(Some(address)) = decoder.abbreviation_address(index) {
+                add(address, StringSource::Abbreviation);
+            }
+        }
+        for (i, &byte) in self.memory.iter().enumerate().skip(high_memory) {
+            if INLINE_STRING_OPCODES.contains(&byte) {
+                add(i + 1, StringSource::Operand);
+            }
+        }
+        // A 16-bit value often matches a packed address by chance, so only the word aligned tables
+        // below high memory and the operands of print_paddr are searched.
+        let tables = self.memory.get(..high_memory).unwrap_or(self.memory);
+        for word in tables.chunks_exact(2) {
+            let packed = u16::from_be_bytes([word[0], word[1]]);
+            add(self.unpack_string_address(packed), StringSource::Reference);
+        }
+        for window in self.memory.windows(3).skip(high_memory) {
+            if window[0] == PRINT_PADDR_OPCODE {
+                let packed = u16::from_be_bytes([window[1], window[2]]);
+                add(self.unpack_string_address(packed), StringSource::Operand);
+            }
+        }
+        if discovery == StringDiscovery::Scan {
+            let scale = self.header.packed_address_scale();
+            for start in (high_memory.next_multiple_of(scale)..self.memory.len()).step_by(scale) {
+                add(start, StringSource::Rom);
+            }
+        }
+
+        // A string also decodes from any packed-aligned address inside it, or just before it, so
+        // of the candidates that share an end only the best is kept, along with any that code
+        // points straight at.
+        let mut strings: Vec<(ParsedString, usize)> = found.into_values().collect();
+        strings.sort_by_key(|(string, end)| (*end, Reverse(string.source), string.start_address));
+        let mut ends = HashSet::new();
+        strings.retain(|(string, end)| ends.insert(*end) || string.source >= StringSource::Operand);
+        strings.sort_by_key(|(string, _)| string.start_address);
+        strings.into_iter().map(|(string, _)| string).collect()
+    }
+
+    /// The checksum as the standard defines it: the sum of every byte after the header up to the
+    /// header's file length, modulo 0x10000. Bytes past the end of the file count as zero.
+    pub fn compute_checksum(&self) -> u16 {
+        let end = self.header.file_length.min(self.memory.len());
+        self.memory
+            .get(ZCODE_HEADER_SIZE..end)
+            .unwrap_or_default()
+            .iter()
+            .fold(0u16, |sum, &b| sum.wrapping_add(b as u16))
+    }
+
+    /// Checks the checksum, that the file is as long as the header says, and that the tables the
+    /// header points at are in the file and in the part of memory they belong in.
+    pub fn integrity_report(&self) -> IntegrityReport {
+        let header = &self.header;
+        let computed_checksum = self.compute_checksum();
+        let mut checks = Vec::new();
+        let mut check = |passed: bool, description: String| {
+            checks.push(IntegrityCheck {
+                description,
+                passed,
+            })
+        };
+
+        // Versions 1 and 2 have neither a length nor a checksum.
+        if header.file_length != 0 {
+            check(
+                computed_checksum == header.checksum,
+                format!(
+                    "Checksum: header says 0x{:04X}, computed 0x{computed_checksum:04X}",
+                    header.checksum
+                ),
+            );
+            check(
+                self.memory.len() >= header.file_length,
+                format!(
+                    "File length ({}) is at least the header's ({})",
+                    self.memory.len(),
+                    header.file_length
+                ),
+            );
+        }
+        check(
+            header.static_memory as usize >= ZCODE_HEADER_SIZE,
+            format!(
+                "Dynamic memory ({} bytes) holds the {ZCODE_HEADER_SIZE} byte header",
+                header.static_memory
+            ),
+        );
+        let mut tables = vec![
+            ("high_memory", header.high_memory),
+            ("dictionary", header.dictionary),
+        ];
+        // Version 1 has no abbreviations.
+        if header.version >= 2 {
+            tables.push(("abbreviations", header.abbreviations));
+        }
+        for (name, address) in tables {
+            check(
+                (address as usize) < self.memory.len(),
+                format!("{name} ({address}) is inside the file"),
+            );
+        }
+        for (name, address) in [
+            ("object_table", header.object_table),
+            ("globals", header.globals),
+        ] {
+            check(
+                address >= ZCODE_HEADER_SIZE as u16 && address < header.static_memory,
+                format!(
+                    "{name} ({address}) is in dynamic memory, before static_memory ({})",
+                    header.static_memory
+                ),
+            );
+        }
+
+        IntegrityReport {
+            computed_checksum: computed_checksum as u32,
+            checks,
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use super::zcode_reader::ZcodeReader;
+use super::FileReadErrorKind::InvalidConversion;
+use super::{read_be_u16, read_bytes, read_u8, FileReadError};
+
+/// The characters of the standard alphabets for Z-characters 6 to 31. In A2, Z-character 6 starts
+/// a 10-bit ZSCII code and, from version 2, 7 is a new line, so neither is looked up here.
+const STANDARD_ALPHABETS: [&[u8; 26]; 3] = [
+    b"abcdefghijklmnopqrstuvwxyz",
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZ",
+    b" \r0123456789.,!?_#'\"/\\-:()",
+];
+/// Version 1 has no new line in A2, so it has room for `<`.
+const VERSION_1_A2: &[u8; 26] = b" 0123456789.,!?_#'\"/\\<-:()";
+
+/// What ZSCII 155 onwards mean when the game doesn't give its own Unicode translation table.
+const DEFAULT_UNICODE_TABLE: &str =
+    "äöüÄÖÜß»«ëïÿËÏáéíóúýÁÉÍÓÚÝàèìòùÀÈÌÒÙâêîôûÂÊÎÔÛåÅøØãñõÃÑÕæÆçÇþðÞÐ£œŒ¡¿";
+const FIRST_EXTRA_CHARACTER: u16 = 155;
+
+/// Where the Unicode translation table's address is in the header extension table, in words.
+const UNICODE_TABLE_EXTENSION_WORD: usize = 3;
+
+/// Decodes the Z-machine's packed 5-bit text into strings, using the game's alphabets,
+/// abbreviations and Unicode translation table.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ZsciiDecoder<'a> {
+    memory: &'a [u8],
+    version: u8,
+    abbreviations: usize,
+    /// The ZSCII code of each Z-character from 6 to 31 in A0, A1 and A2.
+    pub alphabets: [[u8; 26]; 3],
+    /// What ZSCII 155 onwards stand for.
+    pub unicode_table: Vec<char>,
+}
+
+/// The shift state while decoding, which is where the versions differ most.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Pending {
+    None,
+    /// The next Z-character picks abbreviation `32 * (n - 1)` onwards.
+    Abbreviation(u8),
+    /// The next two Z-characters are the top and bottom halves of a ZSCII code.
+    ZsciiHigh,
+    ZsciiLow(u8),
+}
+
+impl<'a> ZsciiDecoder<'a> {
+    pub fn new(reader: &ZcodeReader<'a>) -> Result<ZsciiDecoder<'a>, FileReadError> {
+        let header = &reader.header;
+        let memory = reader.memory;
+        let mut alphabets = STANDARD_ALPHABETS.map(|alphabet| *alphabet);
+        if header.version == 1 {
+            alphabets[2] = *VERSION_1_A2;
+        }
+        if header.version >= 5 && header.alphabet_table != 0 {
+            let table = read_bytes(memory, header.alphabet_table as usize, 78)?;
+            for (alphabet, characters) in alphabets.iter_mut().zip(table.chunks_exact(26)) {
+                alphabet.copy_from_slice(characters);
+            }
+        }
+
+        let mut unicode_table: Vec<char> = DEFAULT_UNICODE_TABLE.chars().collect();
+        if header.version >= 5 && header.extension_table != 0 {
+            let extension = header.extension_table as usize;
+            if read_be_u16(memory, extension)? as usize >= UNICODE_TABLE_EXTENSION_WORD {
+                let address =
+                    read_be_u16(memory, extension + UNICODE_TABLE_EXTENSION_WORD * 2)? as usize;
+                if address != 0 {
+                    let count = read_u8(memory, address)? as usize;
+                    unicode_table = (0..count)
+                        .map(|i| {
+                            let code_point = read_be_u16(memory, address + 1 + i * 2)?;
+                            Ok(char::from_u32(code_point as u32).unwrap_or('\u{FFFD}'))
+                        })
+                        .collect::<Result<_, FileReadError>>()?;
+                }
+            }
+        }
+
+        Ok(ZsciiDecoder {
+            memory,
+            version: header.version,
+            abbreviations: header.abbreviations as usize,
+            alphabets,
+            unicode_table,
+        })
+    }
+
+    /// Decodes the string at byte `address`, returning it along with the address just past its
+    /// last word.
+    pub fn decode(&self, address: usize) -> Result<(String, usize), FileReadError> {
+        let mut out = String::new();
+        let end = self.decode_into(address, &mut out, true, None)?;
+        Ok((out, end))
+    }
+
+    /// Decodes the `length` bytes of text at `address`, such as a dictionary word, which stops
+    /// there even if the last word doesn't have its end bit set.
+    pub fn decode_fixed(&self, address: usize, length: usize) -> Result<String, FileReadError> {
+        let mut out = String::new();
+        self.decode_into(address, &mut out, true, Some(address + length))?;
+        Ok(out)
+    }
+
+    /// The byte address of abbreviation `index`, or `None` if the game has no abbreviations.
+    pub fn abbreviation_address(&self, index: usize) -> Result<Option<usize>, FileReadError> {
+        if self.version == 1 || self.abbreviations == 0 {
+            return Ok(None);
+        }
+        let word_address = read_be_u16(self.memory, self.abbreviations + index * 2)?;
+        Ok(Some(word_address as usize * 2))
+    }
+
+    /// How many abbreviations the game's version allows for.
+    pub fn abbreviation_count(&self) -> usize {
+        match self.version {
+            1 => 0,
+            2 => 32,
+            _ => 96,
+        }
+    }
+
+    fn decode_into(
+        &self,
+        address: usize,
+        out: &mut String,
+        allow_abbreviations: bool,
+        end: Option<usize>,
+    ) -> Result<usize, FileReadError> {
+        // Versions 1 and 2 have shift lock as well as a one character shift.
+        let mut locked = 0;
+        let mut shift = None;
+        let mut pending = Pending::None;
+        let mut offset = address;
+        loop {
+            let word = read_be_u16(self.memory, offset)?;
+            for z in [(word >> 10) & 31, (word >> 5) & 31, word & 31].map(|z| z as u8) {
+                let alphabet = shift.take().unwrap_or(locked);
+                pending = match pending {
+                    Pending::Abbreviation(n) => {
+                        if !allow_abbreviations {
+                            return Err(FileReadError::new(InvalidConversion).at(offset));
+                        }
+                        let index = 32 * (n as usize - 1) + z as usize;
+                        if let Some(abbreviation) = self.abbreviation_address(index)? {
+                            self.decode_into(abbreviation, out, false, None)
+                                .map_err(|e| e.context(InvalidConversion).at(offset))?;
+                        }
+                        Pending::None
+                    }
+                    Pending::ZsciiHigh => Pending::ZsciiLow(z),
+                    Pending::ZsciiLow(high) => {
+                        self.push_zscii((high as u16) << 5 | z as u16, out)
+                            .ok_or(FileReadError::new(InvalidConversion).at(offset))?;
+                        Pending::None
+                    }
+                    Pending::None => match (z, self.version) {
+                        (0, _) => {
+                            out.push(' ');
+                            Pending::None
+                        }
+                        (1, 1) => {
+                            out.push('\n');
+                            Pending::None
+                        }
+                        (1, 2) | (1..=3, 3..) => Pending::Abbreviation(z),
+                        (2..=5, 1 | 2) => {
+                            // 2 and 4 move up an alphabet and 3 and 5 down one; 4 and 5 stay.
+                            let next = if z % 2 == 0 {
+                                (locked + 1) % 3
+                            } else {
+                                (locked + 2) % 3
+                            };
+                            if z >= 4 {
+                                locked = next;
+                            } else {
+                                shift = Some(next);
+                            }
+                            Pending::None
+                        }
+                        (4, _) => {
+                            shift = Some(1);
+                            Pending::None
+                        }
+                        (5, _) => {
+                            shift = Some(2);
+                            Pending::None
+                        }
+                        (6, _) if alphabet == 2 => Pending::ZsciiHigh,
+                        (7, 2..) if alphabet == 2 => {
+                            out.push('\n');
+                            Pending::None
+                        }
+                        _ => {
+                            let zscii = self.alphabets[alphabet][z as usize - 6];
+                            self.push_zscii(zscii as u16, out)
+                                .ok_or(FileReadError::new(InvalidConversion).at(offset))?;
+                            Pending::None
+                        }
+                    },
+                };
+            }
+            offset += 2;
+            if word & 0x8000 != 0 || end.is_some_and(|end| offset >= end) {
+                // A construct cut short by the end of the string is ignored.
+                return Ok(offset);
+            }
+        }
+    }
+
+    /// The character ZSCII `code` stands for, or `None` if it isn't one that can be printed.
+    pub fn zscii_char(&self, code: u16) -> Option<char> {
+        match code {
+            // Tab and sentence space only exist in version 6, but are harmless elsewhere.
+            9 => Some('\t'),
+            11 => Some(' '),
+            13 => Some('\n'),
+            32..=126 => Some(code as u8 as char),
+            FIRST_EXTRA_CHARACTER..=251 => self
+                .unicode_table
+                .get((code - FIRST_EXTRA_CHARACTER) as usize)
+                .copied(),
+            _ => None,
+        }
+    }
+
+    /// Appends the character ZSCII `code` stands for, or returns `None` if it isn't one that can
+    /// be printed. ZSCII 0 prints nothing.
+    fn push_zscii(&self, code: u16, out: &mut String) -> Option<()> {
+        if code != 0 {
+            out.push(self.zscii_char(code)?);
+        }
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ABBREVIATIONS: usize = 0x40;
+    const TEXT: usize = 0x60;
+
+    /// Packs Z-characters three to a word, padding with 5s and setting the end bit on the last.
+    fn encode(zchars: &[u16]) -> Vec<u8> {
+        let mut words: Vec<u16> = zchars
+            .chunks(3)
+            .map(|c| {
+                let z = |i: usize| c.get(i).copied().unwrap_or(5);
+                z(0) << 10 | z(1) << 5 | z(2)
+            })
+            .collect();
+        *words.last_mut().unwrap() |= 0x8000;
+        words.iter().flat_map(|w| w.to_be_bytes()).collect()
+    }
+
+    /// A version 3 story whose first two abbreviations are "the" and one that uses the first,
+    /// with `text` encoded at `TEXT`.
+    fn story(text: &[u16]) -> Vec<u8> {
+        let mut memory = vec![0; 0x80];
+        memory[0] = 3;
+        memory[0x18..0x1A].copy_from_slice(&(ABBREVIATIONS as u16).to_be_bytes());
+        memory[ABBREVIATIONS..ABBREVIATIONS + 4].copy_from_slice(&[0, 0x28, 0, 0x2C]);
+        let the = encode(&[25, 13, 10]);
+        memory[0x50..0x52].copy_from_slice(&the);
+        let nested = encode(&[1, 0]);
+        memory[0x58..0x5A].copy_from_slice(&nested);
+        let text = encode(text);
+        memory[TEXT..TEXT + text.len()].copy_from_slice(&text);
+        memory
+    }
+
+    #[test]
+    fn decodes_abbreviations_shifts_and_escapes() {
+        let memory = story(&[
+            1, 0, // abbreviation 0
+            4, 8, // "C" from A1
+            5, 6, 2, 0, // ZSCII 64
+            5, 6, 4, 27, // ZSCII 155, the first extra character
+            5, 9,  // "1" from A2
+            29, // back in A0
+        ]);
+        let reader = ZcodeReader::try_from(&memory[..]).unwrap();
+        let decoder = ZsciiDecoder::new(&reader).unwrap();
+        assert_eq!(decoder.abbreviation_address(0).unwrap(), Some(0x50));
+        assert_eq!(
+            decoder.decode(TEXT).unwrap(),
+            ("theC@ä1x".to_string(), TEXT + 10)
+        );
+    }
+
+    #[test]
+    fn abbreviation_within_abbreviation_is_error() {
+        let memory = story(&[1, 1]);
+        let reader = ZcodeReader::try_from(&memory[..]).unwrap();
+        let decoder = ZsciiDecoder::new(&reader).unwrap();
+        assert!(decoder.decode(TEXT).is_err());
+    }
+}
@@ -1,28 +1,95 @@
 use crate::egui::Ui;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
 use std::fmt::{Debug, Display, Formatter};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
 
 use eframe::egui::{ColorImage, Context, TextureHandle, WidgetText};
 use eframe::{egui, Frame};
 use egui_extras::Column;
 use strum::IntoEnumIterator;
 
-use crate::file_reader::blorb_chunk_types::BlorbChunkType;
-use crate::file_reader::blorb_reader::BlorbReader;
-use crate::file_reader::ulx_reader::ParsedString;
-use crate::file_reader::GameType;
+use crate::file_reader::blorb_chunk_types::{fourcc_to_string, BlorbChunkType};
+use crate::file_reader::blorb_reader::{
+    BlorbReader, Chunk, Loop, Palette, Placeholder, Ratio, Repeats, Resolution,
+    ResourceDescriptions, SongSample,
+};
+use crate::file_reader::dictionary::{Dictionary, DictionaryEntry};
+use crate::file_reader::disassembler::{Disassembler, ListingLine};
+use crate::file_reader::ifiction::IFictionMetadata;
+use crate::file_reader::tracker::TrackerModule;
+use crate::file_reader::ulx_reader::{
+    IntegrityReport, ParsedString, StringDiscovery, StringSource,
+};
+use crate::file_reader::zcode_objects::{ObjectTable, ZObject};
+use crate::file_reader::{FileReadError, GameType};
+use crate::sound::analysis::{Levels, Spectrogram, Waveform, CLIP_LEVEL, SPECTROGRAM_FLOOR_DB};
+use crate::sound::output::WavOutput;
+use crate::sound::{decode_sound, Pcm, Player, SoundError};
 
 mod file_reader;
+mod sound;
 mod strings;
 
+/// The largest the cover art is drawn on the Games tab, in points.
+const COVER_SIZE: f32 = 256.0;
+const WAVEFORM_HEIGHT: f32 = 160.0;
+const SPECTROGRAM_HEIGHT: f32 = 200.0;
+/// Frames per spectrogram FFT, giving 512 frequency bins.
+const SPECTROGRAM_WINDOW: usize = 1024;
+const SPECTROGRAM_COLUMNS: usize = 1024;
+/// The waveform zooms in as far as 1/4096 of the sound.
+const MAX_ZOOM_LEVEL: usize = 12;
+const SWATCH_SIZE: f32 = 20.0;
+const MAX_PLACEHOLDER_SIZE: f32 = 8192.0;
+const SWATCHES_PER_ROW: usize = 16;
+
 #[derive(Default)]
 struct EguiApp {
     current_menu: Menus,
     current_tab: Tabs,
     loaded_game: Option<GameType<'static>>,
-    loaded_images: HashMap<i32, TextureHandle>,
+    /// The checks of the game's story file, which sum the whole file, once they've been run.
+    integrity_report: Option<IntegrityReport>,
+    /// The `IFmd` chunk's iFiction record, once it's been parsed.
+    metadata: Option<Result<Option<IFictionMetadata>, FileReadError>>,
+    /// The `RDes` chunk's descriptions, once they've been parsed.
+    descriptions: Option<Result<ResourceDescriptions<'static>, FileReadError>>,
+    /// Decoded pictures, with `None` for placeholders and pictures that couldn't be decoded.
+    loaded_images: HashMap<i32, Option<TextureHandle>>,
+    /// The `Plte` chunk's palette, once it's been parsed.
+    palette: Option<Result<Option<Palette>, FileReadError>>,
+    /// How many colours each picture uses that aren't in the `Plte` chunk's palette.
+    palette_mismatches: Option<PaletteMismatches>,
+    /// The `Reso` chunk's window sizes and picture ratios, once they've been parsed.
+    resolution: Option<Result<Option<Resolution>, FileReadError>>,
+    /// The `Loop` chunk's repeat counts, along with the entries for sounds that don't exist, once
+    /// they've been read.
+    loops: Option<Result<(Loop, Vec<i32>), FileReadError>>,
     image_tab_data: ImageTabData,
+    sound_tab_data: SoundTabData,
+    sound_details: HashMap<i32, SoundDetails>,
+    decoded_sounds: HashMap<i32, DecodedSound>,
+    sound_views: HashMap<i32, SoundView>,
+    player: Player,
     parsed_strings: Option<Vec<ParsedString>>,
+    strings_tab_data: StringsTabData,
+    /// The Z-code object table, along with the objects that aren't among their parent's
+    /// children, once it's been read.
+    object_table: Option<Result<(ObjectTable, Vec<u16>), FileReadError>>,
+    objects_tab_data: ObjectsTabData,
+    /// The game's dictionary once it's been looked for, which for Glulx means disassembling.
+    dictionary: Option<Result<Option<Dictionary>, FileReadError>>,
+    dictionary_tab_data: DictionaryTabData,
+    disassembly: Option<Vec<ListingLine>>,
+    load_error: Option<LoadError>,
+}
+
+struct LoadError {
+    path: String,
+    error: Box<dyn Error>,
 }
 
 impl EguiApp {
@@ -30,73 +97,303 @@ impl EguiApp {
         ctx.egui_ctx.set_visuals(egui::Visuals::dark());
         egui_extras::install_image_loaders(&ctx.egui_ctx);
 
-        let game_path = "test_games/imagetest.gblorb";
-        let bytes: &'static [u8] = std::fs::read(game_path)
-            .expect("Unable to open specified path")
-            .leak();
-        let game = bytes
-            .try_into()
-            .expect("Unable to parse game file. Not actually a game?");
-
-        EguiApp {
-            loaded_game: Some(game),
-            ..self
+        // Games are recognised by their contents, so any of .gblorb, .ulx, .zblorb, .z5 or .z8 can
+        // be given.
+        let game_path = std::env::args()
+            .nth(1)
+            .unwrap_or_else(|| "test_games/imagetest.gblorb".to_string());
+        match Self::load_game(&game_path) {
+            Ok(game) => EguiApp {
+                loaded_game: Some(game),
+                ..self
+            },
+            Err(error) => EguiApp {
+                load_error: Some(LoadError {
+                    path: game_path,
+                    error,
+                }),
+                ..self
+            },
+        }
+    }
+
+    fn load_game(game_path: &str) -> Result<GameType<'static>, Box<dyn Error>> {
+        let bytes: &'static [u8] = std::fs::read(game_path)?.leak();
+        Ok(bytes.try_into()?)
+    }
+
+    /// Shows why the game couldn't be opened, one line per error in the cause chain.
+    fn draw_load_error(ui: &mut Ui, load_error: &LoadError) {
+        ui.heading(format!("Unable to open {}", load_error.path));
+        ui.colored_label(egui::Color32::RED, load_error.error.to_string());
+        let mut cause = load_error.error.source();
+        while let Some(error) = cause {
+            ui.label(format!("Caused by: {error}"));
+            cause = error.source();
         }
     }
 
     fn draw_current_tab(&mut self, ui: &mut Ui) {
         match self.current_tab {
             Tabs::Games => self.draw_games_tab(ui),
+            Tabs::Metadata => self.draw_metadata_tab(ui),
             Tabs::Images => self.draw_images_tab(ui),
             Tabs::Sounds => self.draw_sound_tab(ui),
             Tabs::Strings => self.draw_strings_tab(ui),
+            Tabs::Objects => self.draw_objects_tab(ui),
+            Tabs::Dictionary => self.draw_dictionary_tab(ui),
+            Tabs::Disassembly => self.draw_disassembly_tab(ui),
+            Tabs::Chunks => self.draw_chunks_tab(ui),
+            Tabs::Other => self.draw_other_chunks_tab(ui),
         }
     }
 
     fn draw_games_tab(&mut self, ui: &mut Ui) {
-        let game = match &self.loaded_game {
-            Some(GameType::Blorb(b)) => b.get_exec(0).expect("Blorb had no games"),
-            Some(GameType::Ulx(u)) => u.clone(),
-            None => panic!("Tried to draw the game tab without loaded game"),
+        let Some(loaded_game) = &self.loaded_game else {
+            panic!("Tried to draw the game tab without loaded game");
+        };
+        let cover = match loaded_game {
+            GameType::Blorb(b) => b
+                .frontispiece()
+                .and_then(|(id, _)| Self::image_texture(&mut self.loaded_images, ui.ctx(), b, id)),
+            _ => None,
         };
+        let integrity_report = &mut self.integrity_report;
         egui::scroll_area::ScrollArea::vertical().show(ui, |ui| {
-            ui.vertical(|ui| {
-                ui.heading("Game Header");
-                ui.label(game.header.to_string());
-                ui.heading("Debugging Header");
-                ui.label(game.debugging_header.to_string());
+            ui.horizontal_top(|ui| {
+                if let Some(cover) = cover {
+                    ui.add(egui::Image::new(&cover).max_size(egui::vec2(COVER_SIZE, COVER_SIZE)));
+                }
+                ui.vertical(|ui| {
+                    if let Some(game) = loaded_game.get_exec() {
+                        ui.heading("Game Header");
+                        ui.label(game.header.to_string());
+                        let report =
+                            integrity_report.get_or_insert_with(|| game.integrity_report());
+                        Self::draw_integrity_report(ui, report);
+                        ui.heading("Debugging Header");
+                        ui.label(game.debugging_header.to_string());
+                    } else if let Some(game) = loaded_game.get_zcode() {
+                        ui.heading(format!("Z-machine Version {} Header", game.header.version));
+                        ui.label(game.header.to_string());
+                        let report =
+                            integrity_report.get_or_insert_with(|| game.integrity_report());
+                        Self::draw_integrity_report(ui, report);
+                    } else {
+                        ui.heading("No Glulx or Z-code game found in this file");
+                    }
+                });
             });
         });
     }
 
+    fn draw_integrity_report(ui: &mut Ui, report: &IntegrityReport) {
+        if report.passed() {
+            ui.heading("Integrity: passed");
+        } else {
+            ui.heading("Integrity: FAILED");
+        }
+        report.checks.iter().for_each(|check| {
+            let (color, result) = if check.passed {
+                (egui::Color32::GREEN, "PASS")
+            } else {
+                (egui::Color32::RED, "FAIL")
+            };
+            ui.colored_label(color, format!("{result} {}", check.description));
+        });
+    }
+
+    fn draw_metadata_tab(&mut self, ui: &mut Ui) {
+        let Some(GameType::Blorb(b)) = &self.loaded_game else {
+            ui.heading("Only Blorb files carry iFiction metadata");
+            return;
+        };
+        let metadata = match self.metadata.get_or_insert_with(|| b.metadata()) {
+            Ok(Some(metadata)) => metadata,
+            Ok(None) => {
+                ui.heading("This file has no IFmd chunk");
+                return;
+            }
+            Err(error) => {
+                ui.heading("Unable to read the iFiction metadata");
+                error.chain().for_each(|e| {
+                    ui.colored_label(egui::Color32::RED, e.to_string());
+                });
+                return;
+            }
+        };
+        ui.heading(metadata.title.as_deref().unwrap_or("Untitled"));
+        egui::scroll_area::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("metadata")
+                .num_columns(2)
+                .striped(true)
+                .show(ui, |ui| {
+                    metadata.fields().into_iter().for_each(|(name, value)| {
+                        ui.strong(name);
+                        ui.label(value);
+                        ui.end_row();
+                    });
+                });
+        });
+    }
+
+    /// The texture for picture `id`, decoding and uploading it the first time it's asked for.
+    /// `None` if there's no such picture, it's a placeholder, or it can't be decoded.
+    fn image_texture(
+        loaded_images: &mut HashMap<i32, Option<TextureHandle>>,
+        ctx: &Context,
+        b: &BlorbReader,
+        id: i32,
+    ) -> Option<TextureHandle> {
+        let chunk = b.get_image(id)?;
+        let handle = loaded_images.entry(id).or_insert_with(|| {
+            if chunk.chunk_type == BlorbChunkType::PICTURE_RECT {
+                return None;
+            }
+            let image = image::load_from_memory(chunk.data).ok()?;
+            let size = [image.width() as _, image.height() as _];
+            let image_buffer = image.to_rgba8();
+            let pixels = image_buffer.as_flat_samples();
+            let picture = ColorImage::from_rgba_unmultiplied(size, pixels.as_slice());
+            Some(ctx.load_texture(format!("picture {id}"), picture, Default::default()))
+        });
+        handle.clone()
+    }
+
+    /// The file's resource descriptions, parsed the first time they're asked for. If the `RDes`
+    /// chunk is damaged, shows why and returns `None`.
+    fn descriptions<'d>(
+        ui: &mut Ui,
+        descriptions: &'d mut Option<Result<ResourceDescriptions<'static>, FileReadError>>,
+        b: &BlorbReader<'static>,
+    ) -> Option<&'d ResourceDescriptions<'static>> {
+        match descriptions.get_or_insert_with(|| b.resource_descriptions()) {
+            Ok(descriptions) => Some(descriptions),
+            Err(error) => {
+                ui.colored_label(
+                    egui::Color32::RED,
+                    format!("Unable to read resource descriptions: {error}"),
+                );
+                None
+            }
+        }
+    }
+
+    /// Counts the resources in `ids` that have no description, for accessibility review. Draws
+    /// nothing if the descriptions couldn't be read, as that's already been shown.
+    fn draw_missing_descriptions(
+        ui: &mut Ui,
+        descriptions: Option<&ResourceDescriptions>,
+        usage: BlorbChunkType,
+        ids: &[i32],
+    ) {
+        let Some(descriptions) = descriptions else {
+            return;
+        };
+        let missing = ids
+            .iter()
+            .filter(|&&id| descriptions.get(usage, id).is_none())
+            .count();
+        if missing > 0 {
+            ui.colored_label(
+                egui::Color32::ORANGE,
+                format!("{missing} of {} have no description", ids.len()),
+            );
+        }
+    }
+
+    fn draw_description(
+        ui: &mut Ui,
+        descriptions: Option<&ResourceDescriptions>,
+        usage: BlorbChunkType,
+        id: i32,
+    ) {
+        let Some(descriptions) = descriptions else {
+            return;
+        };
+        match descriptions.get(usage, id) {
+            Some(text) => {
+                ui.add(egui::Label::new(text).truncate())
+                    .on_hover_text(text);
+            }
+            None => {
+                ui.colored_label(egui::Color32::ORANGE, "No description");
+            }
+        }
+    }
+
     fn draw_images_tab(&mut self, ui: &mut Ui) {
         let count = egui::SidePanel::left("image_options")
             .show_inside(ui, |ui| {
                 if let Some(GameType::Blorb(b)) = &self.loaded_game {
                     let mut ids = b.image_ids();
                     ids.sort();
-                    ids.iter()
-                        .find(|&&id| ui.button(format!("{id}")).clicked())
-                        .map(|&id| {
-                            self.image_tab_data.selected_image = Some(
-                                self.loaded_images
-                                    .entry(id)
-                                    .or_insert_with(|| {
-                                        let picture_bytes = b.get_image(id).unwrap().data;
-                                        let image = image::load_from_memory(picture_bytes)
-                                            .expect("Chunk provided invalid image");
-                                        let size = [image.width() as _, image.height() as _];
-                                        let image_buffer = image.to_rgba8();
-                                        let pixels = image_buffer.as_flat_samples();
-                                        let picture = ColorImage::from_rgba_unmultiplied(
-                                            size,
-                                            pixels.as_slice(),
-                                        );
-                                        ui.ctx().load_texture("", picture, Default::default())
-                                    })
-                                    .clone(),
-                            )
+                    let cover = b.frontispiece_id();
+                    let palette = match self.palette.get_or_insert_with(|| b.palette()) {
+                        Ok(palette) => palette.as_ref(),
+                        Err(error) => {
+                            ui.colored_label(
+                                egui::Color32::RED,
+                                format!("Unable to read the palette: {error}"),
+                            );
+                            None
+                        }
+                    };
+                    if let Some(palette @ Palette::Colours(_)) = palette {
+                        let mismatches = self.palette_mismatches.get_or_insert_with(|| {
+                            PaletteMismatches::new(ui.ctx(), b, palette, &ids)
                         });
+                        let stopped = mismatches.receive();
+                        if mismatches.counts.len() < ids.len() {
+                            if stopped {
+                                ui.colored_label(
+                                    egui::Color32::RED,
+                                    "The palette check stopped unexpectedly",
+                                );
+                            } else {
+                                ui.horizontal(|ui| {
+                                    ui.spinner();
+                                    ui.label("Checking pictures against the palette...");
+                                });
+                            }
+                        }
+                    }
+                    let descriptions = Self::descriptions(ui, &mut self.descriptions, b);
+                    Self::draw_missing_descriptions(
+                        ui,
+                        descriptions,
+                        BlorbChunkType::PICTURE,
+                        &ids,
+                    );
+                    if let Some(&id) = ids.iter().find(|&&id| {
+                        ui.horizontal(|ui| {
+                            let mut label = format!("{id}");
+                            if Some(id) == cover {
+                                label.push_str(" (cover)");
+                            }
+                            if b.get_image(id).is_some_and(|chunk| {
+                                chunk.chunk_type == BlorbChunkType::PICTURE_RECT
+                            }) {
+                                label.push_str(" (placeholder)");
+                            }
+                            let clicked = ui.button(label).clicked();
+                            if let Some(mismatches) = &self.palette_mismatches {
+                                if let Some(Some(count @ 1..)) = mismatches.counts.get(&id) {
+                                    ui.colored_label(
+                                        egui::Color32::ORANGE,
+                                        format!("{count} colours outside the palette"),
+                                    );
+                                }
+                            }
+                            Self::draw_description(ui, descriptions, BlorbChunkType::PICTURE, id);
+                            clicked
+                        })
+                        .inner
+                    }) {
+                        self.image_tab_data.selected_id = Some(id);
+                        self.image_tab_data.selected_image =
+                            Self::image_texture(&mut self.loaded_images, ui.ctx(), b, id);
+                    }
                     ids.len()
                 } else {
                     0
@@ -108,29 +405,177 @@ impl EguiApp {
                 ui.heading("No images found in this game file");
                 return;
             }
+            if let Some(Ok(Some(palette))) = &self.palette {
+                Self::draw_palette(ui, palette);
+            }
+            let resolution = match &self.loaded_game {
+                Some(GameType::Blorb(b)) => {
+                    match self.resolution.get_or_insert_with(|| b.resolution()) {
+                        Ok(resolution) => resolution.as_ref(),
+                        Err(error) => {
+                            ui.colored_label(
+                                egui::Color32::RED,
+                                format!("Unable to read picture resolution: {error}"),
+                            );
+                            None
+                        }
+                    }
+                }
+                _ => None,
+            };
+            let scale = resolution
+                .map(|resolution| Self::draw_window_size(ui, resolution, &mut self.image_tab_data));
             let sa = egui::scroll_area::ScrollArea::both();
             sa.show(ui, |ui| {
                 if let Some(handle) = &self.image_tab_data.selected_image {
-                    ui.image((handle.id(), ui.available_size_before_wrap()));
+                    match scale {
+                        Some(scale) => {
+                            let size =
+                                handle.size_vec2() * scale as f32 / ui.ctx().pixels_per_point();
+                            ui.image((handle.id(), size));
+                        }
+                        None => {
+                            ui.image((handle.id(), ui.available_size_before_wrap()));
+                        }
+                    }
+                } else if let (Some(GameType::Blorb(b)), Some(id)) =
+                    (&self.loaded_game, self.image_tab_data.selected_id)
+                {
+                    match b.get_placeholder(id) {
+                        Ok(Some(placeholder)) => {
+                            Self::draw_placeholder(ui, placeholder, scale.unwrap_or(1.0))
+                        }
+                        Ok(None) => {
+                            ui.colored_label(
+                                egui::Color32::RED,
+                                format!("Picture {id} could not be decoded"),
+                            );
+                        }
+                        Err(error) => error.chain().for_each(|e| {
+                            ui.colored_label(egui::Color32::RED, e.to_string());
+                        }),
+                    }
                 }
             });
         });
     }
 
+    /// A placeholder picture as a crossed out box of the size it would be drawn at `scale`, labelled
+    /// with its size in pixels.
+    fn draw_placeholder(ui: &mut Ui, placeholder: Placeholder, scale: f64) {
+        let size = egui::vec2(placeholder.width as f32, placeholder.height as f32) * scale as f32
+            / ui.ctx().pixels_per_point();
+        // Keep nonsense sizes from asking egui for an enormous canvas.
+        let size = size.min(egui::Vec2::splat(MAX_PLACEHOLDER_SIZE));
+        let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+        let stroke = ui.visuals().widgets.noninteractive.fg_stroke;
+        let painter = ui.painter();
+        painter.rect_stroke(rect, 0.0, stroke, egui::StrokeKind::Inside);
+        painter.line_segment([rect.left_top(), rect.right_bottom()], stroke);
+        painter.line_segment([rect.right_top(), rect.left_bottom()], stroke);
+        ui.label(format!("Placeholder, {placeholder} pixels"));
+    }
+
+    /// The palette's colours as a grid of swatches, each showing its index and hex code on hover.
+    fn draw_palette(ui: &mut Ui, palette: &Palette) {
+        egui::CollapsingHeader::new(format!("Palette: {palette}")).show(ui, |ui| match palette {
+            Palette::DirectColour(_) => {
+                ui.label("Pictures may use any colour");
+            }
+            Palette::Colours(colours) => {
+                egui::Grid::new("palette")
+                    .spacing([2.0, 2.0])
+                    .show(ui, |ui| {
+                        for (i, &[r, g, b]) in colours.iter().enumerate() {
+                            let (rect, response) = ui.allocate_exact_size(
+                                egui::vec2(SWATCH_SIZE, SWATCH_SIZE),
+                                egui::Sense::hover(),
+                            );
+                            ui.painter()
+                                .rect_filled(rect, 2.0, egui::Color32::from_rgb(r, g, b));
+                            response.on_hover_text(format!("{i}: #{r:02X}{g:02X}{b:02X}"));
+                            if (i + 1) % SWATCHES_PER_ROW == 0 {
+                                ui.end_row();
+                            }
+                        }
+                    });
+            }
+        });
+    }
+
+    /// A slider for the width of an imagined game window, kept to the standard size's shape and
+    /// the `Reso` chunk's limits, along with how the selected picture is scaled in it. Returns
+    /// that scale.
+    fn draw_window_size(
+        ui: &mut Ui,
+        resolution: &Resolution,
+        image_tab_data: &mut ImageTabData,
+    ) -> f64 {
+        let (standard_width, standard_height) = resolution.standard;
+        let min_width = match resolution.min.0 {
+            0 => (standard_width / 4).max(1),
+            width => width,
+        };
+        let max_width = match resolution.max.0 {
+            0 => standard_width.saturating_mul(4).max(min_width),
+            width => width.max(min_width),
+        };
+        let width = image_tab_data
+            .window_width
+            .get_or_insert(standard_width.clamp(min_width, max_width));
+        ui.horizontal(|ui| {
+            ui.label("Window width");
+            ui.add(egui::Slider::new(width, min_width..=max_width).suffix(" px"));
+            if ui.button("Standard").clicked() {
+                *width = standard_width.clamp(min_width, max_width);
+            }
+        });
+        let height = (*width as u64 * standard_height as u64 / standard_width.max(1) as u64) as u32;
+        let Some(id) = image_tab_data.selected_id else {
+            return 1.0;
+        };
+        let scale = resolution.scale(id, *width, height);
+        let limit = |ratio: Ratio| {
+            ratio
+                .value()
+                .map_or("none".to_string(), |_| ratio.to_string())
+        };
+        ui.label(match resolution.images.get(&id) {
+            Some(scaling) => format!(
+                "In a {width} × {height} window, picture {id} is drawn at {scale:.2}× \
+                 (ratio {}, minimum {}, maximum {})",
+                scaling.ratio,
+                limit(scaling.min),
+                limit(scaling.max)
+            ),
+            None => format!("Picture {id} has no scaling rules, so it's drawn at its own size"),
+        });
+        scale
+    }
+
     fn draw_sound_tab(&mut self, ui: &mut Ui) {
         fn draw_sub_header(
-            b: &BlorbReader,
+            descriptions: Option<&ResourceDescriptions>,
             ui: &mut Ui,
-            chunk_type: BlorbChunkType,
+            ids: &[i32],
             heading: impl Into<WidgetText>,
+            loops: &Loop,
+            selected: &mut Option<i32>,
         ) -> usize {
-            let mut ids = b.get_ids(chunk_type);
-            ids.sort();
-
             if !ids.is_empty() {
                 egui::CollapsingHeader::new(heading).show(ui, |ui| {
-                    ids.iter().for_each(|id| {
-                        ui.label(format!("{id}"));
+                    EguiApp::draw_missing_descriptions(
+                        ui,
+                        descriptions,
+                        BlorbChunkType::SOUND,
+                        ids,
+                    );
+                    ids.iter().for_each(|&id| {
+                        ui.horizontal(|ui| {
+                            ui.selectable_value(selected, Some(id), format!("{id}"));
+                            ui.weak(loops.repeats(id).to_string());
+                            EguiApp::draw_description(ui, descriptions, BlorbChunkType::SOUND, id);
+                        });
                     });
                 });
             }
@@ -138,41 +583,443 @@ impl EguiApp {
             ids.len()
         }
 
-        let count = egui::SidePanel::left("sound_options")
-            .show_inside(ui, |ui| {
-                if let Some(GameType::Blorb(b)) = &self.loaded_game {
-                    draw_sub_header(b, ui, BlorbChunkType::SOUND, "Sound")
-                        + draw_sub_header(b, ui, BlorbChunkType::SOUND_MOD, "MOD Sounds")
-                        + draw_sub_header(b, ui, BlorbChunkType::SOUND_SONG, "Songs")
-                } else {
-                    0
-                }
+        let Some(GameType::Blorb(b)) = &self.loaded_game else {
+            ui.heading("No sounds found in this game file");
+            return;
+        };
+        let mut ids = b.sound_ids();
+        ids.sort();
+        let format_ids = |formats: &[BlorbChunkType]| -> Vec<i32> {
+            ids.iter()
+                .copied()
+                .filter(|&id| {
+                    b.get_sound(id)
+                        .is_some_and(|chunk| formats.contains(&chunk.chunk_type))
+                })
+                .collect()
+        };
+        let known_formats = [
+            BlorbChunkType::SOUND_AIFF,
+            BlorbChunkType::SOUND_OGG,
+            BlorbChunkType::SOUND_MOD,
+            BlorbChunkType::SOUND_SONG,
+        ];
+        let other_ids: Vec<i32> = ids
+            .iter()
+            .copied()
+            .filter(|&id| {
+                !b.get_sound(id)
+                    .is_some_and(|chunk| known_formats.contains(&chunk.chunk_type))
             })
-            .inner;
+            .collect();
+
+        let loops = &*self.loops.get_or_insert_with(|| {
+            b.loops().map(|loops| {
+                let unknown_ids = loops.unknown_ids(&ids);
+                (loops, unknown_ids)
+            })
+        });
+        let selected = &mut self.sound_tab_data.selected_sound;
+        egui::SidePanel::left("sound_options").show_inside(ui, |ui| {
+            let loops = match loops {
+                Ok((loops, unknown_ids)) => {
+                    for id in unknown_ids {
+                        ui.colored_label(
+                            egui::Color32::RED,
+                            format!("Loop entry for sound {id}, which doesn't exist"),
+                        );
+                    }
+                    loops
+                }
+                Err(error) => {
+                    ui.colored_label(
+                        egui::Color32::RED,
+                        format!("Unable to read sound loops: {error}"),
+                    );
+                    &Loop::default()
+                }
+            };
+            let descriptions = EguiApp::descriptions(ui, &mut self.descriptions, b);
+            let aiff = format_ids(&[BlorbChunkType::SOUND_AIFF]);
+            let ogg = format_ids(&[BlorbChunkType::SOUND_OGG]);
+            let mods = format_ids(&[BlorbChunkType::SOUND_MOD]);
+            let songs = format_ids(&[BlorbChunkType::SOUND_SONG]);
+            draw_sub_header(descriptions, ui, &aiff, "AIFF Sounds", loops, selected);
+            draw_sub_header(descriptions, ui, &ogg, "Ogg Vorbis Sounds", loops, selected);
+            draw_sub_header(descriptions, ui, &mods, "MOD Sounds", loops, selected);
+            draw_sub_header(descriptions, ui, &songs, "Songs", loops, selected);
+            draw_sub_header(
+                descriptions,
+                ui,
+                &other_ids,
+                "Other Sounds",
+                loops,
+                selected,
+            );
+        });
         egui::CentralPanel::default().show_inside(ui, |ui| {
-            if count == 0 {
+            if ids.is_empty() {
                 ui.heading("No sounds found in this game file");
                 return;
             }
-            //TODO
+            let Some((id, chunk)) = selected.and_then(|id| Some((id, b.get_sound(id)?))) else {
+                ui.label("Select a sound to see its format");
+                return;
+            };
+            ui.heading(format!("Sound {id}"));
+            let repeats = loops
+                .as_ref()
+                .map(|(loops, _)| loops.repeats(id))
+                .unwrap_or_default();
+            ui.label(format!("The game {repeats}"));
+            let details = self
+                .sound_details
+                .entry(id)
+                .or_insert_with(|| SoundDetails::new(b, id, chunk.chunk_type));
+            match &details.text {
+                Ok(Some(details)) => {
+                    ui.label(details);
+                }
+                Err(error) => error.chain().for_each(|e| {
+                    ui.colored_label(egui::Color32::RED, e.to_string());
+                }),
+                Ok(None) => {
+                    ui.label(format!("Format: {}", fourcc_to_string(chunk.fourcc)));
+                    ui.label(format!("Length: {} bytes", chunk.data.len()));
+                }
+            }
+            if let Some((module, song_samples)) = &details.tracker {
+                Self::draw_tracker_samples(ui, module, song_samples.as_deref());
+            }
+            ui.separator();
+            let Some(pcm) = Self::draw_playback(
+                ui,
+                &mut self.player,
+                &mut self.decoded_sounds,
+                &mut self.sound_tab_data.playback_error,
+                b,
+                id,
+                repeats,
+            ) else {
+                return;
+            };
+            ui.separator();
+            let view = self
+                .sound_views
+                .entry(id)
+                .or_insert_with(|| SoundView::new(ui.ctx(), id, pcm));
+            Self::draw_sound_view(
+                ui,
+                view,
+                &mut self.sound_tab_data.waveform_view,
+                self.player.position(),
+            );
         });
     }
 
-    fn draw_strings_tab(&mut self, ui: &mut Ui) {
-        let strings =
-            self.parsed_strings
-                .get_or_insert_with(|| match self.loaded_game.as_ref().unwrap() {
-                    GameType::Ulx(game) => game.parse_strings(),
-                    GameType::Blorb(game) => game.get_exec(0).unwrap().parse_strings(),
+    /// The samples of a MOD or, when `song_samples` is given, a `SONG`, whose samples are listed
+    /// with the sound resources holding their data. Samples that refer to sounds which are
+    /// missing or aren't AIFFs are reported.
+    fn draw_tracker_samples(
+        ui: &mut Ui,
+        module: &TrackerModule,
+        song_samples: Option<&[SongSample]>,
+    ) {
+        let problems = song_samples.map_or(0, |samples| {
+            samples
+                .iter()
+                .filter(|s| matches!(s, SongSample::Missing(_) | SongSample::NotAiff(_)))
+                .count()
+        });
+        if problems > 0 {
+            ui.colored_label(
+                egui::Color32::RED,
+                format!("{problems} samples refer to sounds that can't be used"),
+            );
+        }
+        egui::CollapsingHeader::new(format!("Samples ({})", module.samples.len())).show(ui, |ui| {
+            egui::Grid::new("tracker_samples")
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong("#");
+                    ui.strong("Name");
+                    ui.strong(if song_samples.is_some() {
+                        "Sound"
+                    } else {
+                        "Length"
+                    });
+                    ui.strong("Volume");
+                    ui.strong("Finetune");
+                    ui.strong("Loop");
+                    ui.end_row();
+                    for (i, sample) in module.samples.iter().enumerate() {
+                        ui.label(format!("{}", i + 1));
+                        ui.label(&sample.name);
+                        match song_samples.and_then(|samples| samples.get(i)) {
+                            Some(song_sample @ SongSample::Aiff(_)) => {
+                                ui.label(song_sample.to_string());
+                            }
+                            Some(song_sample @ SongSample::Unused) => {
+                                ui.weak(song_sample.to_string());
+                            }
+                            Some(song_sample) => {
+                                ui.colored_label(egui::Color32::RED, song_sample.to_string());
+                            }
+                            None => {
+                                ui.label(format!("{} bytes", sample.length));
+                            }
+                        }
+                        ui.label(format!("{}", sample.volume));
+                        ui.label(format!("{}", sample.finetune));
+                        if sample.loop_length > 2 {
+                            ui.label(format!(
+                                "{}..{}",
+                                sample.loop_start,
+                                sample.loop_start + sample.loop_length
+                            ));
+                        } else {
+                            ui.label("");
+                        }
+                        ui.end_row();
+                    }
                 });
+        });
+    }
+
+    /// Play, stop and seek controls for sound `id`, which plays `repeats` times. The sound is
+    /// decoded on a worker thread the first time it's shown, as long songs take a while. Returns
+    /// the decoded sound, or `None` if it's still being decoded or couldn't be.
+    fn draw_playback(
+        ui: &mut Ui,
+        player: &mut Player,
+        decoded_sounds: &mut HashMap<i32, DecodedSound>,
+        playback_error: &mut Option<String>,
+        b: &BlorbReader<'static>,
+        id: i32,
+        repeats: Repeats,
+    ) -> Option<Arc<Pcm>> {
+        let decoded = decoded_sounds.entry(id).or_insert_with(|| {
+            let (sender, receiver) = mpsc::channel();
+            let (b, ctx) = (b.clone(), ui.ctx().clone());
+            thread::spawn(move || {
+                let _ = sender.send(decode_sound(&b, id));
+                ctx.request_repaint();
+            });
+            DecodedSound::Decoding(receiver)
+        });
+        if let DecodedSound::Decoding(receiver) = decoded {
+            *decoded = match receiver.try_recv() {
+                Ok(result) => {
+                    DecodedSound::Decoded(result.map(Arc::new).map_err(|e| e.to_string()))
+                }
+                Err(TryRecvError::Empty) => {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Decoding...");
+                    });
+                    return None;
+                }
+                Err(TryRecvError::Disconnected) => {
+                    DecodedSound::Decoded(Err("The decoder stopped unexpectedly".to_string()))
+                }
+            };
+        }
+        let pcm = match decoded {
+            DecodedSound::Decoded(Ok(pcm)) => pcm.clone(),
+            DecodedSound::Decoded(Err(error)) => {
+                ui.colored_label(egui::Color32::RED, format!("Unable to decode: {error}"));
+                return None;
+            }
+            DecodedSound::Decoding(_) => return None,
+        };
+        if player.loaded_id() != Some(id) {
+            player.load(id, pcm.clone(), repeats);
+            *playback_error = None;
+        }
+
+        let rate = pcm.sample_rate.max(1) as f64;
+        let mut result = Ok(());
+        ui.horizontal(|ui| {
+            if player.is_playing() {
+                if ui.button("Stop").clicked() {
+                    player.stop();
+                }
+            } else if ui.button("Play").clicked() {
+                result = player.play();
+            }
+            let mut seconds = player.position() as f64 / rate;
+            let slider = egui::Slider::new(&mut seconds, 0.0..=pcm.duration())
+                .fixed_decimals(2)
+                .suffix(" s");
+            if ui.add(slider).changed() {
+                result = player.seek((seconds * rate) as usize);
+            }
+        });
+        if player.is_silent() {
+            ui.colored_label(egui::Color32::ORANGE, "No audio device: playback is silent");
+        }
+        if let Some(error) = result.err().or_else(|| player.take_error()) {
+            *playback_error = Some(error.to_string());
+        }
+        if let Some(error) = playback_error {
+            ui.colored_label(egui::Color32::RED, error.as_str());
+        }
+        if player.is_playing() {
+            ui.ctx().request_repaint();
+        }
+        Some(pcm)
+    }
+
+    /// Each channel's levels, then the waveform, zoomed to part of the sound, and the
+    /// spectrogram of the same part. `playhead` is marked on both if it's in view.
+    fn draw_sound_view(
+        ui: &mut Ui,
+        view: &SoundView,
+        waveform_view: &mut WaveformView,
+        playhead: usize,
+    ) {
+        for (channel, levels) in view.levels.iter().enumerate() {
+            let text = format!(
+                "Channel {}: peak {:.1} dBFS, RMS {:.1} dBFS",
+                channel + 1,
+                levels.peak_db(),
+                levels.rms_db()
+            );
+            if levels.clipped > 0 {
+                ui.colored_label(
+                    egui::Color32::RED,
+                    format!("{text}, {} clipped samples", levels.clipped),
+                );
+            } else if levels.is_silent() {
+                ui.colored_label(egui::Color32::ORANGE, format!("{text}, silent"));
+            } else {
+                ui.label(text);
+            }
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Zoom");
+            ui.add(
+                egui::Slider::new(&mut waveform_view.zoom_level, 0..=MAX_ZOOM_LEVEL)
+                    .custom_formatter(|level, _| format!("{}x", 1u32 << level as u32)),
+            );
+            ui.label("Scroll");
+            ui.add(egui::Slider::new(&mut waveform_view.scroll, 0.0..=1.0).show_value(false));
+        });
+        let frames = view.waveform.pcm().frames().max(1);
+        let visible = (frames >> waveform_view.zoom_level).max(1);
+        let start = ((frames - visible) as f64 * waveform_view.scroll) as usize;
+        let end = start + visible;
+        let playhead_x = |rect: egui::Rect| {
+            (start..end)
+                .contains(&playhead)
+                .then(|| rect.left() + (playhead - start) as f32 / visible as f32 * rect.width())
+        };
+
+        let (rect, _) = ui.allocate_exact_size(
+            egui::vec2(ui.available_width(), WAVEFORM_HEIGHT),
+            egui::Sense::hover(),
+        );
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, egui::Color32::from_gray(24));
+        let y = |sample: f32| rect.center().y - sample.clamp(-1.0, 1.0) * rect.height() / 2.0;
+        let columns = view.waveform.columns(start, end, rect.width() as usize);
+        for (x, &(low, high)) in columns.iter().enumerate() {
+            let color = if high >= CLIP_LEVEL || low <= -CLIP_LEVEL {
+                egui::Color32::RED
+            } else {
+                egui::Color32::LIGHT_GREEN
+            };
+            let x = rect.left() + x as f32 + 0.5;
+            painter.line_segment(
+                [egui::pos2(x, y(high)), egui::pos2(x, y(low) + 1.0)],
+                egui::Stroke::new(1.0, color),
+            );
+        }
+        if let Some(x) = playhead_x(rect) {
+            painter.vline(
+                x,
+                rect.y_range(),
+                egui::Stroke::new(1.0, egui::Color32::WHITE),
+            );
+        }
+
+        ui.label(format!(
+            "Spectrogram, 0 to {} Hz",
+            view.waveform.pcm().sample_rate / 2
+        ));
+        let uv = egui::Rect::from_x_y_ranges(
+            start as f32 / frames as f32..=end as f32 / frames as f32,
+            0.0..=1.0,
+        );
+        let response = ui.add(
+            egui::Image::new(&view.spectrogram)
+                .uv(uv)
+                .fit_to_exact_size(egui::vec2(ui.available_width(), SPECTROGRAM_HEIGHT)),
+        );
+        if let Some(x) = playhead_x(response.rect) {
+            ui.painter().vline(
+                x,
+                response.rect.y_range(),
+                egui::Stroke::new(1.0, egui::Color32::WHITE),
+            );
+        }
+    }
+
+    fn draw_strings_tab(&mut self, ui: &mut Ui) {
+        let tab_data = &mut self.strings_tab_data;
+        egui::TopBottomPanel::top("string_options").show_inside(ui, |ui| {
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_label("Minimum source")
+                    .selected_text(tab_data.min_source.to_string())
+                    .show_ui(ui, |ui| {
+                        StringSource::iter().for_each(|source| {
+                            ui.selectable_value(
+                                &mut tab_data.min_source,
+                                source,
+                                source.to_string(),
+                            );
+                        });
+                    });
+                let mut scan = tab_data.discovery == StringDiscovery::Scan;
+                if ui.checkbox(&mut scan, "Scan every byte").changed() {
+                    tab_data.discovery = if scan {
+                        StringDiscovery::Scan
+                    } else {
+                        StringDiscovery::Referenced
+                    };
+                    self.parsed_strings = None;
+                }
+            });
+        });
+        let StringsTabData {
+            min_source,
+            discovery,
+        } = self.strings_tab_data;
+        let strings = self.parsed_strings.get_or_insert_with(|| {
+            let loaded_game = self.loaded_game.as_ref().unwrap();
+            loaded_game
+                .get_exec()
+                .map(|game| game.parse_strings(discovery))
+                .or_else(|| {
+                    loaded_game
+                        .get_zcode()
+                        .map(|game| game.parse_strings(discovery))
+                })
+                .unwrap_or_default()
+        });
+        let shown: Vec<&ParsedString> = strings.iter().filter(|s| s.source >= min_source).collect();
         egui::CentralPanel::default().show_inside(ui, |ui| {
             egui_extras::TableBuilder::new(ui)
-                .columns(Column::auto(), 2)
+                .columns(Column::auto(), 3)
                 .column(Column::remainder())
                 .header(20.0, |mut header| {
                     header.col(|ui| {
                         ui.heading("Type");
                     });
+                    header.col(|ui| {
+                        ui.heading("Source");
+                    });
                     header.col(|ui| {
                         ui.heading("Address");
                     });
@@ -181,22 +1028,443 @@ impl EguiApp {
                     });
                 })
                 .body(|body| {
-                    body.rows(18.0, strings.len(), |mut row| {
-                        let row_index = row.index();
+                    body.rows(18.0, shown.len(), |mut row| {
+                        let string = shown[row.index()];
+                        row.col(|ui| {
+                            ui.label(string.string_type.to_string());
+                        });
                         row.col(|ui| {
-                            ui.label(format!("{:?}", strings[row_index].string_type));
+                            ui.label(string.source.to_string());
                         });
                         row.col(|ui| {
-                            ui.label(format!("{}", strings[row_index].start_address));
+                            ui.label(format!("{}", string.start_address));
                         });
                         row.col(|ui| {
-                            ui.label(format!("{:?}", strings[row_index].data));
+                            ui.label(format!("{:?}", string.data));
                         });
                     });
                 });
         });
     }
 
+    fn draw_objects_tab(&mut self, ui: &mut Ui) {
+        let Some(game) = self.loaded_game.as_ref().unwrap().get_zcode() else {
+            ui.heading("Only Z-code games have an object table");
+            return;
+        };
+        let object_table = self.object_table.get_or_insert_with(|| {
+            let table = ObjectTable::try_from(&game)?;
+            let misplaced = table.misplaced();
+            Ok((table, misplaced))
+        });
+        let (table, misplaced) = match object_table {
+            Ok((table, misplaced)) => (&*table, &*misplaced),
+            Err(error) => {
+                ui.heading("Unable to read the object table");
+                error.chain().for_each(|e| {
+                    ui.colored_label(egui::Color32::RED, e.to_string());
+                });
+                return;
+            }
+        };
+        let selected = &mut self.objects_tab_data.selected_object;
+        egui::SidePanel::left("object_tree").show_inside(ui, |ui| {
+            ui.heading(format!("{} objects", table.objects.len()));
+            egui::scroll_area::ScrollArea::vertical().show(ui, |ui| {
+                for root in table.roots() {
+                    Self::draw_object_node(ui, table, root, selected, &mut Vec::new());
+                }
+                if !misplaced.is_empty() {
+                    ui.colored_label(
+                        egui::Color32::ORANGE,
+                        format!("Not among their parent's children: {misplaced:?}"),
+                    );
+                }
+            });
+        });
+        egui::CentralPanel::default().show_inside(ui, |ui| {
+            match selected.and_then(|number| table.get(number)) {
+                Some(object) => Self::draw_object(ui, table, object, selected),
+                None => {
+                    ui.heading("Select an object");
+                }
+            }
+        });
+    }
+
+    /// Draws `number` and, when expanded, its children. `ancestors` catches trees that loop.
+    fn draw_object_node(
+        ui: &mut Ui,
+        table: &ObjectTable,
+        number: u16,
+        selected: &mut Option<u16>,
+        ancestors: &mut Vec<u16>,
+    ) {
+        let Some(object) = table.get(number) else {
+            return;
+        };
+        if ancestors.contains(&number) {
+            ui.colored_label(egui::Color32::RED, format!("{object} (loops back)"));
+            return;
+        }
+        let children = table.children(number);
+        if children.is_empty() {
+            if ui
+                .selectable_label(*selected == Some(number), object.to_string())
+                .clicked()
+            {
+                *selected = Some(number);
+            }
+            return;
+        }
+        ancestors.push(number);
+        let response = egui::CollapsingHeader::new(object.to_string())
+            .id_salt(number)
+            .show(ui, |ui| {
+                for child in children {
+                    Self::draw_object_node(ui, table, child, selected, ancestors);
+                }
+            });
+        ancestors.pop();
+        if response.header_response.clicked() {
+            *selected = Some(number);
+        }
+    }
+
+    fn draw_object(ui: &mut Ui, table: &ObjectTable, object: &ZObject, selected: &mut Option<u16>) {
+        ui.heading(object.to_string());
+        egui::Grid::new("object_links")
+            .num_columns(2)
+            .show(ui, |ui| {
+                for (name, number) in [
+                    ("Parent", object.parent),
+                    ("Sibling", object.sibling),
+                    ("Child", object.child),
+                ] {
+                    ui.strong(name);
+                    match table.get(number) {
+                        Some(linked) => {
+                            if ui.link(linked.to_string()).clicked() {
+                                *selected = Some(number);
+                            }
+                        }
+                        None if number == 0 => {
+                            ui.label("nothing");
+                        }
+                        None => {
+                            ui.colored_label(
+                                egui::Color32::RED,
+                                format!("{number}, which isn't in the table"),
+                            );
+                        }
+                    }
+                    ui.end_row();
+                }
+                ui.strong("Attributes");
+                ui.label(
+                    object
+                        .attributes
+                        .iter()
+                        .map(|a| a.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                );
+                ui.end_row();
+                ui.strong("Property table");
+                ui.monospace(format!("{:04X}", object.property_table));
+                ui.end_row();
+            });
+
+        ui.heading("Properties");
+        egui_extras::TableBuilder::new(ui)
+            .columns(Column::auto(), 3)
+            .column(Column::remainder())
+            .header(20.0, |mut header| {
+                header.col(|ui| {
+                    ui.heading("Number");
+                });
+                header.col(|ui| {
+                    ui.heading("Address");
+                });
+                header.col(|ui| {
+                    ui.heading("Length");
+                });
+                header.col(|ui| {
+                    ui.heading("Data");
+                });
+            })
+            .body(|body| {
+                body.rows(18.0, object.properties.len(), |mut row| {
+                    let property = &object.properties[row.index()];
+                    row.col(|ui| {
+                        ui.label(property.number.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.monospace(format!("{:04X}", property.address));
+                    });
+                    row.col(|ui| {
+                        ui.label(format!("{} bytes", property.data.len()));
+                    });
+                    row.col(|ui| {
+                        let bytes: Vec<String> =
+                            property.data.iter().map(|b| format!("{b:02X}")).collect();
+                        ui.monospace(bytes.join(" "));
+                    });
+                });
+            });
+    }
+
+    fn draw_dictionary_tab(&mut self, ui: &mut Ui) {
+        let dictionary = self.dictionary.get_or_insert_with(|| {
+            let loaded_game = self.loaded_game.as_ref().unwrap();
+            match (loaded_game.get_zcode(), loaded_game.get_exec()) {
+                (Some(game), _) => Dictionary::try_from(&game).map(Some),
+                (None, Some(game)) => Ok(Dictionary::find_glulx(&game)),
+                (None, None) => Ok(None),
+            }
+        });
+        let dictionary = match dictionary {
+            Ok(Some(dictionary)) => dictionary,
+            Ok(None) => {
+                ui.heading("No dictionary found in this file");
+                return;
+            }
+            Err(error) => {
+                ui.heading("Unable to read the dictionary");
+                error.chain().for_each(|e| {
+                    ui.colored_label(egui::Color32::RED, e.to_string());
+                });
+                return;
+            }
+        };
+        let search = &mut self.dictionary_tab_data.search;
+        egui::TopBottomPanel::top("dictionary_options").show_inside(ui, |ui| {
+            ui.label(format!(
+                "{} words at {:08X}, found through {}, in {} byte entries",
+                dictionary.entries.len(),
+                dictionary.address,
+                dictionary.location,
+                dictionary.entry_length
+            ));
+            if !dictionary.word_separators.is_empty() {
+                let separators: String = dictionary.word_separators.iter().collect();
+                ui.label(format!("Word separators: {separators:?}"));
+            }
+            ui.horizontal(|ui| {
+                ui.label("Search");
+                ui.text_edit_singleline(search);
+            });
+        });
+        let query = search.to_lowercase();
+        let shown: Vec<&DictionaryEntry> = dictionary
+            .entries
+            .iter()
+            .filter(|entry| entry.word.to_lowercase().contains(&query))
+            .collect();
+        egui::CentralPanel::default().show_inside(ui, |ui| {
+            egui_extras::TableBuilder::new(ui)
+                .columns(Column::auto(), 3)
+                .column(Column::remainder())
+                .header(20.0, |mut header| {
+                    header.col(|ui| {
+                        ui.heading("Address");
+                    });
+                    header.col(|ui| {
+                        ui.heading("Word");
+                    });
+                    header.col(|ui| {
+                        ui.heading("Inform flags");
+                    });
+                    header.col(|ui| {
+                        ui.heading("Data");
+                    });
+                })
+                .body(|body| {
+                    body.rows(18.0, shown.len(), |mut row| {
+                        let entry = shown[row.index()];
+                        row.col(|ui| {
+                            ui.monospace(format!("{:08X}", entry.address));
+                        });
+                        row.col(|ui| {
+                            ui.label(&entry.word);
+                        });
+                        row.col(|ui| {
+                            ui.label(entry.flag_names().join(", "));
+                        });
+                        row.col(|ui| {
+                            let bytes: Vec<String> =
+                                entry.data.iter().map(|b| format!("{b:02X}")).collect();
+                            ui.monospace(bytes.join(" "));
+                        });
+                    });
+                });
+        });
+    }
+
+    fn draw_disassembly_tab(&mut self, ui: &mut Ui) {
+        let lines = self.disassembly.get_or_insert_with(|| {
+            match self.loaded_game.as_ref().unwrap().get_exec() {
+                Some(game) => {
+                    let disassembler = Disassembler::new(&game);
+                    disassembler.listing(&disassembler.disassemble())
+                }
+                None => Vec::new(),
+            }
+        });
+        egui::CentralPanel::default().show_inside(ui, |ui| {
+            if self.loaded_game.as_ref().unwrap().get_exec().is_none() {
+                ui.heading("Only Glulx games can be disassembled");
+                return;
+            }
+            if lines.is_empty() {
+                ui.heading("No code found from the start function");
+                return;
+            }
+            egui_extras::TableBuilder::new(ui)
+                .columns(Column::auto(), 2)
+                .column(Column::remainder())
+                .header(20.0, |mut header| {
+                    header.col(|ui| {
+                        ui.heading("Address");
+                    });
+                    header.col(|ui| {
+                        ui.heading("Instruction");
+                    });
+                    header.col(|ui| {
+                        ui.heading("References");
+                    });
+                })
+                .body(|body| {
+                    body.rows(18.0, lines.len(), |mut row| {
+                        let line = &lines[row.index()];
+                        row.col(|ui| {
+                            ui.monospace(format!("{:08X}", line.address));
+                        });
+                        row.col(|ui| {
+                            ui.monospace(&line.text);
+                        });
+                        row.col(|ui| {
+                            ui.label(&line.comment);
+                        });
+                    });
+                });
+        });
+    }
+
+    fn draw_chunks_tab(&mut self, ui: &mut Ui) {
+        fn contents(chunk: &Chunk) -> String {
+            match chunk.chunk_type {
+                BlorbChunkType::RELEASE_NUMBER if chunk.data.len() >= 2 => {
+                    format!(
+                        "Release {}",
+                        u16::from_be_bytes([chunk.data[0], chunk.data[1]])
+                    )
+                }
+                _ => chunk.text().unwrap_or_default(),
+            }
+        }
+
+        let Some(GameType::Blorb(b)) = &self.loaded_game else {
+            ui.heading("Only Blorb files have chunks");
+            return;
+        };
+        if let Some(error) = b.chunk_error() {
+            error.chain().for_each(|e| {
+                ui.colored_label(egui::Color32::RED, e.to_string());
+            });
+        }
+        let chunks = b.chunks();
+        egui_extras::TableBuilder::new(ui)
+            .columns(Column::auto(), 4)
+            .column(Column::remainder())
+            .header(20.0, |mut header| {
+                header.col(|ui| {
+                    ui.heading("Offset");
+                });
+                header.col(|ui| {
+                    ui.heading("Type");
+                });
+                header.col(|ui| {
+                    ui.heading("Length");
+                });
+                header.col(|ui| {
+                    ui.heading("Resource");
+                });
+                header.col(|ui| {
+                    ui.heading("Contents");
+                });
+            })
+            .body(|body| {
+                body.rows(18.0, chunks.len(), |mut row| {
+                    let chunk = &chunks[row.index()];
+                    row.col(|ui| {
+                        ui.monospace(format!("{:08X}", chunk.offset));
+                    });
+                    row.col(|ui| {
+                        ui.monospace(fourcc_to_string(chunk.fourcc));
+                    });
+                    row.col(|ui| {
+                        ui.label(format!("{} bytes", chunk.data.len()));
+                    });
+                    row.col(|ui| {
+                        if let Some((usage, id)) = b.resource_at(chunk.offset) {
+                            ui.label(format!("{} {id}", fourcc_to_string(usage as u32)));
+                        }
+                    });
+                    row.col(|ui| {
+                        ui.label(contents(chunk));
+                    });
+                });
+            });
+    }
+
+    fn draw_other_chunks_tab(&mut self, ui: &mut Ui) {
+        let Some(GameType::Blorb(b)) = &self.loaded_game else {
+            ui.heading("Only Blorb files have chunks");
+            return;
+        };
+        let chunks = b.other_chunks();
+        ui.heading("Other chunks");
+        if chunks.is_empty() {
+            ui.label("Every chunk in this file is a known type");
+            return;
+        }
+        egui_extras::TableBuilder::new(ui)
+            .columns(Column::auto(), 3)
+            .column(Column::remainder())
+            .header(20.0, |mut header| {
+                header.col(|ui| {
+                    ui.heading("Usage");
+                });
+                header.col(|ui| {
+                    ui.heading("ID");
+                });
+                header.col(|ui| {
+                    ui.heading("Type");
+                });
+                header.col(|ui| {
+                    ui.heading("Length");
+                });
+            })
+            .body(|body| {
+                body.rows(18.0, chunks.len(), |mut row| {
+                    let chunk = &chunks[row.index()];
+                    row.col(|ui| {
+                        ui.monospace(fourcc_to_string(chunk.usage));
+                    });
+                    row.col(|ui| {
+                        ui.label(format!("{}", chunk.id));
+                    });
+                    row.col(|ui| {
+                        ui.monospace(fourcc_to_string(chunk.chunk.fourcc));
+                    });
+                    row.col(|ui| {
+                        ui.label(format!("{} bytes", chunk.chunk.data.len()));
+                    });
+                });
+            });
+    }
+
     fn draw_menu_from_enum<I, D>(ui: &mut Ui, current_option: &mut D, options: I)
     where
         I: Iterator<Item=D>,
@@ -227,9 +1495,15 @@ impl Display for Menus {
 enum Tabs {
     #[default]
     Games,
+    Metadata,
     Images,
     Sounds,
     Strings,
+    Objects,
+    Dictionary,
+    Disassembly,
+    Chunks,
+    Other,
 }
 
 impl Display for Tabs {
@@ -241,6 +1515,189 @@ impl Display for Tabs {
 #[derive(Clone, Default, Eq, PartialEq, Hash)]
 struct ImageTabData {
     selected_image: Option<TextureHandle>,
+    selected_id: Option<i32>,
+    /// The width of the window pictures are previewed in, which starts at the standard width.
+    window_width: Option<u32>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct SoundTabData {
+    selected_sound: Option<i32>,
+    /// Why the last play or seek failed, until another sound is selected.
+    playback_error: Option<String>,
+    waveform_view: WaveformView,
+}
+
+/// A sound being decoded on a worker thread, then what came of it.
+enum DecodedSound {
+    Decoding(Receiver<Result<Pcm, SoundError>>),
+    Decoded(Result<Arc<Pcm>, String>),
+}
+
+/// How many colours each picture uses that aren't in the palette, counted on a worker thread
+/// which sends each picture's count as it's done.
+struct PaletteMismatches {
+    receiver: Receiver<(i32, Option<usize>)>,
+    /// `None` for pictures that couldn't be decoded to check.
+    counts: HashMap<i32, Option<usize>>,
+}
+
+impl PaletteMismatches {
+    fn new(ctx: &Context, b: &BlorbReader<'static>, palette: &Palette, ids: &[i32]) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let (b, palette, ids, ctx) = (b.clone(), palette.clone(), ids.to_vec(), ctx.clone());
+        thread::spawn(move || {
+            for id in ids {
+                let data = b.get_image(id).map_or(&[][..], |c| c.data);
+                if sender
+                    .send((id, colours_outside_palette(data, &palette)))
+                    .is_err()
+                {
+                    return;
+                }
+                ctx.request_repaint();
+            }
+        });
+        PaletteMismatches {
+            receiver,
+            counts: HashMap::new(),
+        }
+    }
+
+    /// Takes the counts the worker has sent since last time, returning whether it has stopped.
+    fn receive(&mut self) -> bool {
+        loop {
+            match self.receiver.try_recv() {
+                Ok((id, count)) => {
+                    self.counts.insert(id, count);
+                }
+                Err(TryRecvError::Empty) => return false,
+                Err(TryRecvError::Disconnected) => return true,
+            }
+        }
+    }
+}
+
+/// Which part of a sound the waveform and spectrogram show.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+struct WaveformView {
+    /// The view shows 1 / 2^`zoom_level` of the sound.
+    zoom_level: usize,
+    /// How far through the sound the view starts, from 0 to 1.
+    scroll: f64,
+}
+
+/// What the Sounds tab says about a sound's format, worked out when it's first selected.
+struct SoundDetails {
+    /// The parsed sound's description, or `None` for formats that aren't parsed.
+    text: Result<Option<String>, FileReadError>,
+    /// A MOD or `SONG`'s module, along with the sound resource each of a `SONG`'s samples refers
+    /// to.
+    tracker: Option<(TrackerModule<'static>, Option<Vec<SongSample>>)>,
+}
+
+impl SoundDetails {
+    fn new(b: &BlorbReader<'static>, id: i32, chunk_type: BlorbChunkType) -> Self {
+        let (text, tracker) = match chunk_type {
+            BlorbChunkType::SOUND_AIFF => (b.get_aiff(id).map(|s| s.map(|s| s.to_string())), None),
+            BlorbChunkType::SOUND_OGG => (b.get_ogg(id).map(|s| s.map(|s| s.to_string())), None),
+            BlorbChunkType::SOUND_MOD => match b.get_mod(id) {
+                Ok(module) => (
+                    Ok(module.as_ref().map(|m| m.to_string())),
+                    module.map(|m| (m, None)),
+                ),
+                Err(error) => (Err(error), None),
+            },
+            BlorbChunkType::SOUND_SONG => match b.get_song(id) {
+                Ok(song) => (
+                    Ok(song.as_ref().map(|s| s.to_string())),
+                    song.map(|s| {
+                        let samples = b.song_samples(&s);
+                        (s, Some(samples))
+                    }),
+                ),
+                Err(error) => (Err(error), None),
+            },
+            _ => (Ok(None), None),
+        };
+        Self { text, tracker }
+    }
+}
+
+/// What the Sounds tab draws of a decoded sound, worked out when it's first selected.
+struct SoundView {
+    levels: Vec<Levels>,
+    waveform: Waveform,
+    spectrogram: TextureHandle,
+}
+
+impl SoundView {
+    fn new(ctx: &Context, id: i32, pcm: Arc<Pcm>) -> Self {
+        let spectrogram = Spectrogram::new(&pcm, SPECTROGRAM_WINDOW, SPECTROGRAM_COLUMNS);
+        let size = [spectrogram.columns.len().max(1), spectrogram.bins.max(1)];
+        let mut image = ColorImage::new(size, egui::Color32::BLACK);
+        for (x, column) in spectrogram.columns.iter().enumerate() {
+            // Low frequencies go at the bottom.
+            for (bin, &db) in column.iter().enumerate() {
+                image[(x, spectrogram.bins - 1 - bin)] =
+                    heat_color(1.0 - db / SPECTROGRAM_FLOOR_DB);
+            }
+        }
+        SoundView {
+            levels: Levels::of(&pcm),
+            waveform: Waveform::new(pcm),
+            spectrogram: ctx.load_texture(format!("spectrogram {id}"), image, Default::default()),
+        }
+    }
+}
+
+/// How many distinct colours the picture in `data` uses that aren't in `palette`, ignoring fully
+/// transparent pixels. `None` if the picture can't be decoded.
+fn colours_outside_palette(data: &[u8], palette: &Palette) -> Option<usize> {
+    let image = image::load_from_memory(data).ok()?.to_rgba8();
+    let outside: HashSet<[u8; 3]> = image
+        .pixels()
+        .filter(|pixel| pixel[3] > 0)
+        .map(|pixel| [pixel[0], pixel[1], pixel[2]])
+        .filter(|&rgb| !palette.contains(rgb))
+        .collect();
+    Some(outside.len())
+}
+
+/// Black through blue, purple and orange to yellow as `t` goes from 0 to 1.
+fn heat_color(t: f32) -> egui::Color32 {
+    let t = t.clamp(0.0, 1.0);
+    let channel = |start: f32, end: f32| ((t - start) / (end - start)).clamp(0.0, 1.0);
+    egui::Color32::from_rgb(
+        (channel(0.3, 0.8) * 255.0) as u8,
+        (channel(0.6, 1.0) * 255.0) as u8,
+        ((channel(0.0, 0.3) - channel(0.6, 0.9)).max(0.0) * 255.0) as u8,
+    )
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+struct StringsTabData {
+    min_source: StringSource,
+    discovery: StringDiscovery,
+}
+
+impl Default for StringsTabData {
+    fn default() -> Self {
+        StringsTabData {
+            min_source: StringSource::Reference,
+            discovery: StringDiscovery::Referenced,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+struct ObjectsTabData {
+    selected_object: Option<u16>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+struct DictionaryTabData {
+    search: String,
 }
 
 impl eframe::App for EguiApp {
@@ -253,13 +1710,39 @@ impl eframe::App for EguiApp {
                     EguiApp::draw_menu_from_enum(ui, &mut self.current_tab, Tabs::iter());
                 })
             });
-        egui::CentralPanel::default().show(ctx, |ui| {
-            self.draw_current_tab(ui);
+        egui::CentralPanel::default().show(ctx, |ui| match &self.load_error {
+            Some(load_error) => EguiApp::draw_load_error(ui, load_error),
+            None => self.draw_current_tab(ui),
         });
     }
 }
 
+/// Writes sound `id` of the Blorb at `game_path` to a WAV file at `wav_path`, repeated as its
+/// `Loop` entry says.
+fn export_wav(game_path: &str, id: &str, wav_path: &str) -> Result<(), Box<dyn Error>> {
+    let GameType::Blorb(b) = EguiApp::load_game(game_path)? else {
+        return Err("Only Blorb files have sounds".into());
+    };
+    let id = id.parse()?;
+    let pcm = decode_sound(&b, id)?;
+    let repeats = b.loops().map(|loops| loops.repeats(id)).unwrap_or_default();
+    let mut player = Player::new(Box::new(WavOutput::new(wav_path)));
+    player.load(id, Arc::new(pcm), repeats);
+    Ok(player.play()?)
+}
+
 fn main() {
+    // `blorb_browser <game> --wav <sound id> <file>` writes a sound out without opening a window.
+    let args: Vec<String> = std::env::args().collect();
+    if let [_, game_path, flag, id, wav_path] = args.as_slice() {
+        if flag == "--wav" {
+            if let Err(error) = export_wav(game_path, id, wav_path) {
+                eprintln!("Unable to write sound {id} to {wav_path}: {error}");
+                std::process::exit(1);
+            }
+            return;
+        }
+    }
     let app = EguiApp::default();
     let native_options = eframe::NativeOptions::default();
     eframe::run_native(
@@ -0,0 +1,187 @@
+use std::sync::Arc;
+
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+
+use super::Pcm;
+
+/// Samples at or above this are taken to have been clipped when the sound was made.
+pub const CLIP_LEVEL: f32 = 0.999;
+/// A channel whose peak is below this many dBFS is treated as silent.
+pub const SILENCE_DB: f32 = -60.0;
+/// The quietest level the spectrogram shows, in dBFS.
+pub const SPECTROGRAM_FLOOR_DB: f32 = -100.0;
+
+/// The peak and RMS level of one channel of a sound.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Levels {
+    pub peak: f32,
+    pub rms: f32,
+    /// How many samples are at or above `CLIP_LEVEL`.
+    pub clipped: usize,
+}
+
+impl Levels {
+    /// The levels of each channel of `pcm`.
+    pub fn of(pcm: &Pcm) -> Vec<Levels> {
+        let channels = pcm.channels as usize;
+        (0..channels)
+            .map(|channel| {
+                let (peak, sum_squares, clipped) =
+                    pcm.samples.iter().skip(channel).step_by(channels).fold(
+                        (0f32, 0f64, 0),
+                        |(peak, sum, clipped), &s| {
+                            let s_abs = s.abs();
+                            (
+                                peak.max(s_abs),
+                                sum + (s as f64) * (s as f64),
+                                clipped + (s_abs >= CLIP_LEVEL) as usize,
+                            )
+                        },
+                    );
+                let frames = pcm.frames().max(1);
+                Levels {
+                    peak,
+                    rms: (sum_squares / frames as f64).sqrt() as f32,
+                    clipped,
+                }
+            })
+            .collect()
+    }
+
+    pub fn peak_db(&self) -> f32 {
+        to_db(self.peak)
+    }
+
+    pub fn rms_db(&self) -> f32 {
+        to_db(self.rms)
+    }
+
+    pub fn is_silent(&self) -> bool {
+        self.peak_db() < SILENCE_DB
+    }
+}
+
+/// An amplitude as decibels relative to full scale, with silence as negative infinity.
+pub fn to_db(amplitude: f32) -> f32 {
+    20.0 * amplitude.log10()
+}
+
+/// The outline of a sound, as the lowest and highest sample across all channels over each
+/// stretch of time. Blocks of frames are summarised up front, so that zoomed out views of long
+/// sounds don't need to look at every sample each time they're drawn.
+#[derive(Debug, Clone)]
+pub struct Waveform {
+    pcm: Arc<Pcm>,
+    blocks: Vec<(f32, f32)>,
+}
+
+const WAVEFORM_BLOCK_FRAMES: usize = 256;
+
+impl Waveform {
+    pub fn new(pcm: Arc<Pcm>) -> Self {
+        let channels = (pcm.channels as usize).max(1);
+        let blocks = pcm
+            .samples
+            .chunks(WAVEFORM_BLOCK_FRAMES * channels)
+            .map(min_max)
+            .collect();
+        Waveform { pcm, blocks }
+    }
+
+    /// The range of samples in each of `columns` equal slices of the frames from `start` to
+    /// `end`.
+    pub fn columns(&self, start: usize, end: usize, columns: usize) -> Vec<(f32, f32)> {
+        let channels = (self.pcm.channels as usize).max(1);
+        let end = end.min(self.pcm.frames());
+        if columns == 0 || start >= end {
+            return Vec::new();
+        }
+        let frames_per_column = (end - start) as f64 / columns as f64;
+        (0..columns)
+            .map(|column| {
+                let from = start + (column as f64 * frames_per_column) as usize;
+                let to = (start + ((column + 1) as f64 * frames_per_column) as usize)
+                    .max(from + 1)
+                    .min(end);
+                if frames_per_column >= WAVEFORM_BLOCK_FRAMES as f64 {
+                    let blocks = &self.blocks[from / WAVEFORM_BLOCK_FRAMES
+                        ..to.div_ceil(WAVEFORM_BLOCK_FRAMES).min(self.blocks.len())];
+                    merge_ranges(blocks.iter().copied())
+                } else {
+                    min_max(&self.pcm.samples[from * channels..to * channels])
+                }
+            })
+            .collect()
+    }
+
+    pub fn pcm(&self) -> &Pcm {
+        &self.pcm
+    }
+}
+
+/// The lowest and highest of `samples`, or zeros if there are none.
+fn min_max(samples: &[f32]) -> (f32, f32) {
+    merge_ranges(samples.iter().map(|&s| (s, s)))
+}
+
+fn merge_ranges(ranges: impl Iterator<Item = (f32, f32)>) -> (f32, f32) {
+    ranges
+        .reduce(|(lo, hi), (l, h)| (lo.min(l), hi.max(h)))
+        .unwrap_or_default()
+}
+
+/// How loud each frequency is over time, from short Hann windowed FFTs of the sound mixed down to
+/// mono.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Spectrogram {
+    /// Frequency bins per column, from 0 Hz up to half the sample rate.
+    pub bins: usize,
+    /// Frames between the starts of successive columns.
+    pub hop: usize,
+    /// One column per window, each holding the level of every bin in dBFS.
+    pub columns: Vec<Vec<f32>>,
+}
+
+impl Spectrogram {
+    /// Analyses `pcm` with windows of `window` frames, spaced so there are no more than about
+    /// `max_columns` columns but windows don't overlap by more than half.
+    pub fn new(pcm: &Pcm, window: usize, max_columns: usize) -> Self {
+        let channels = (pcm.channels as usize).max(1);
+        let frames = pcm.frames();
+        if frames < window || window == 0 || max_columns == 0 {
+            return Spectrogram::default();
+        }
+        let hop = ((frames - window) / max_columns + 1).max(window / 2);
+        let hann: Vec<f32> = (0..window)
+            .map(|i| {
+                let phase = std::f32::consts::TAU * i as f32 / window as f32;
+                0.5 - 0.5 * phase.cos()
+            })
+            .collect();
+        // A full scale sine comes out of a Hann windowed FFT at a quarter of the window length.
+        let scale = 4.0 / window as f32;
+        let fft = FftPlanner::new().plan_fft_forward(window);
+        let bins = window / 2;
+
+        let columns = (0..=(frames - window) / hop)
+            .map(|column| {
+                let start = column * hop;
+                let mut buffer: Vec<Complex<f32>> = (0..window)
+                    .map(|i| {
+                        let frame =
+                            &pcm.samples[(start + i) * channels..(start + i + 1) * channels];
+                        let mono = frame.iter().sum::<f32>() / channels as f32;
+                        Complex::new(mono * hann[i], 0.0)
+                    })
+                    .collect();
+                fft.process(&mut buffer);
+                buffer[..bins]
+                    .iter()
+                    .map(|c| to_db(c.norm() * scale).max(SPECTROGRAM_FLOOR_DB))
+                    .collect()
+            })
+            .collect();
+        Spectrogram { bins, hop, columns }
+    }
+}
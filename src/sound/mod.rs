@@ -0,0 +1,306 @@
+use std::fmt::{Display, Formatter};
+use std::io::Cursor;
+use std::sync::Arc;
+
+use lewton::inside_ogg::OggStreamReader;
+use lewton::VorbisError;
+
+use crate::file_reader::aiff::AiffSound;
+use crate::file_reader::blorb_chunk_types::{fourcc_to_string, BlorbChunkType};
+use crate::file_reader::blorb_reader::{BlorbReader, Repeats};
+use crate::file_reader::FileReadError;
+use output::AudioOutput;
+
+pub mod analysis;
+pub mod output;
+pub mod tracker;
+
+/// Decoded audio: interleaved samples scaled to between -1 and 1.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Pcm {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub samples: Vec<f32>,
+}
+
+#[derive(Debug)]
+pub enum SoundError {
+    Read(FileReadError),
+    Vorbis(VorbisError),
+    Wav(hound::Error),
+    /// The audio device couldn't be opened or played to.
+    #[cfg_attr(not(feature = "cpal"), allow(dead_code))]
+    Output(String),
+    /// The sound is in a format with no decoder, such as a `SONG` that needs other resources.
+    UnsupportedFormat(u32),
+    NoSuchSound(i32),
+}
+
+impl Display for SoundError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SoundError::Read(e) => write!(f, "{e}"),
+            SoundError::Vorbis(e) => write!(f, "Unable to decode Vorbis audio: {e}"),
+            SoundError::Wav(e) => write!(f, "Unable to write WAV file: {e}"),
+            SoundError::Output(e) => write!(f, "Unable to play audio: {e}"),
+            SoundError::UnsupportedFormat(fourcc) => {
+                write!(f, "{} sounds can't be decoded", fourcc_to_string(*fourcc))
+            }
+            SoundError::NoSuchSound(id) => write!(f, "There is no sound {id}"),
+        }
+    }
+}
+
+impl std::error::Error for SoundError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SoundError::Read(e) => Some(e),
+            SoundError::Vorbis(e) => Some(e),
+            SoundError::Wav(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<FileReadError> for SoundError {
+    fn from(e: FileReadError) -> Self {
+        SoundError::Read(e)
+    }
+}
+
+impl From<VorbisError> for SoundError {
+    fn from(e: VorbisError) -> Self {
+        SoundError::Vorbis(e)
+    }
+}
+
+impl From<hound::Error> for SoundError {
+    fn from(e: hound::Error) -> Self {
+        SoundError::Wav(e)
+    }
+}
+
+impl Pcm {
+    pub fn frames(&self) -> usize {
+        if self.channels == 0 {
+            0
+        } else {
+            self.samples.len() / self.channels as usize
+        }
+    }
+
+    pub fn duration(&self) -> f64 {
+        if self.sample_rate == 0 {
+            0.0
+        } else {
+            self.frames() as f64 / self.sample_rate as f64
+        }
+    }
+}
+
+/// Decodes sound resource `id` to PCM, whichever format it's stored in.
+pub fn decode_sound(b: &BlorbReader, id: i32) -> Result<Pcm, SoundError> {
+    let chunk = b.get_sound(id).ok_or(SoundError::NoSuchSound(id))?;
+    match chunk.chunk_type {
+        BlorbChunkType::SOUND_AIFF => match b.get_aiff(id)? {
+            Some(aiff) => Ok(decode_aiff(&aiff)),
+            None => Err(SoundError::NoSuchSound(id)),
+        },
+        BlorbChunkType::SOUND_OGG => decode_vorbis(chunk.data),
+        BlorbChunkType::SOUND_MOD => match b.get_mod(id)? {
+            Some(module) => Ok(tracker::render(&module)),
+            None => Err(SoundError::NoSuchSound(id)),
+        },
+        _ => Err(SoundError::UnsupportedFormat(chunk.fourcc)),
+    }
+}
+
+/// Converts AIFF's big-endian signed samples, which are left-justified within whole bytes.
+pub fn decode_aiff(aiff: &AiffSound) -> Pcm {
+    let bytes_per_sample = aiff.common.bytes_per_sample();
+    let frames = aiff.sound_data.map(|s| s.frames()).unwrap_or_default();
+    let sample_count =
+        (aiff.common.data_len() as usize).min(frames.len()) / bytes_per_sample.max(1);
+    let samples = frames
+        .chunks_exact(bytes_per_sample.max(1))
+        .take(sample_count)
+        .map(|bytes| {
+            let value = bytes.iter().fold(0i64, |value, &b| (value << 8) | b as i64);
+            let bits = bytes.len() as u32 * 8;
+            // Sign extend from the sample's width, then scale to -1..1.
+            let value = (value << (64 - bits)) >> (64 - bits);
+            value as f32 / (1i64 << (bits - 1)) as f32
+        })
+        .collect();
+    Pcm {
+        sample_rate: aiff.common.sample_rate as u32,
+        channels: aiff.common.channels,
+        samples,
+    }
+}
+
+pub fn decode_vorbis(data: &[u8]) -> Result<Pcm, SoundError> {
+    let mut reader = OggStreamReader::new(Cursor::new(data))?;
+    let mut samples = Vec::new();
+    while let Some(packet) = reader.read_dec_packet_itl()? {
+        samples.extend(packet.iter().map(|&s| s as f32 / 32768.0));
+    }
+    Ok(Pcm {
+        sample_rate: reader.ident_hdr.audio_sample_rate,
+        channels: reader.ident_hdr.audio_channels as u16,
+        samples,
+    })
+}
+
+/// Plays one decoded sound at a time through an `AudioOutput`, remembering where playback is
+/// so that it can be sought while stopped.
+pub struct Player {
+    output: Box<dyn AudioOutput>,
+    /// The resource ID and audio of the loaded sound.
+    loaded: Option<(i32, Arc<Pcm>)>,
+    /// How many times the loaded sound plays through.
+    repeats: Repeats,
+    /// Where playback starts from when `play` is called.
+    position: usize,
+}
+
+impl Default for Player {
+    fn default() -> Self {
+        Player::new(output::default_output())
+    }
+}
+
+impl Player {
+    pub fn new(output: Box<dyn AudioOutput>) -> Self {
+        Player {
+            output,
+            loaded: None,
+            repeats: Repeats::default(),
+            position: 0,
+        }
+    }
+
+    /// Stops whatever is playing and makes `pcm` the sound to play, from the start, `repeats`
+    /// times over.
+    pub fn load(&mut self, id: i32, pcm: Arc<Pcm>, repeats: Repeats) {
+        self.output.stop();
+        self.loaded = Some((id, pcm));
+        self.repeats = repeats;
+        self.position = 0;
+    }
+
+    pub fn loaded_id(&self) -> Option<i32> {
+        self.loaded.as_ref().map(|(id, _)| *id)
+    }
+
+    pub fn play(&mut self) -> Result<(), SoundError> {
+        let Some((_, pcm)) = &self.loaded else {
+            return Ok(());
+        };
+        if self.position >= pcm.frames() {
+            self.position = 0;
+        }
+        self.output.play(pcm.clone(), self.position, self.repeats)
+    }
+
+    /// Stops playback and rewinds to the start.
+    pub fn stop(&mut self) {
+        self.output.stop();
+        self.position = 0;
+    }
+
+    /// Moves playback to `frame`, carrying on playing from there if it was playing.
+    pub fn seek(&mut self, frame: usize) -> Result<(), SoundError> {
+        let playing = self.is_playing();
+        let frames = self.loaded.as_ref().map_or(0, |(_, pcm)| pcm.frames());
+        self.position = frame.min(frames);
+        if playing {
+            self.play()
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.output.position().is_some()
+    }
+
+    /// The frame being played, or where playback will start from if it's stopped.
+    pub fn position(&self) -> usize {
+        self.output.position().unwrap_or(self.position)
+    }
+
+    /// An error the output ran into while playing in the background, if there's been one since
+    /// the last time this was called.
+    pub fn take_error(&mut self) -> Option<SoundError> {
+        self.output.take_error()
+    }
+
+    pub fn is_silent(&self) -> bool {
+        self.output.is_silent()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::output::{AudioOutput, NullOutput, WavOutput};
+    use super::*;
+
+    const AIFF_BLORB: &[u8] = include_bytes!("../../fuzz/corpus/parse_game/aiff_sound.gblorb");
+    const OGG_BLORB: &[u8] = include_bytes!("../../fuzz/corpus/parse_game/ogg_sound.gblorb");
+    const MOD_BLORB: &[u8] = include_bytes!("../../fuzz/corpus/parse_game/mod_sound.gblorb");
+
+    /// Decodes sound `id` and plays it through both headless outputs, checking the WAV file gets
+    /// the sound's format and every sample of both repeats.
+    fn play_sound(blorb: &[u8], id: i32) {
+        let b = BlorbReader::try_from(blorb).unwrap();
+        let pcm = Arc::new(decode_sound(&b, id).unwrap());
+        assert!(pcm.frames() > 0);
+
+        let mut null = NullOutput::default();
+        assert!(null.is_silent());
+        null.play(pcm.clone(), 0, Repeats::Times(2)).unwrap();
+        assert!(null.position().is_some());
+        null.stop();
+        assert_eq!(null.position(), None);
+
+        let path =
+            std::env::temp_dir().join(format!("blorb_browser_{}_{id}.wav", std::process::id()));
+        let mut wav = WavOutput::new(&path);
+        wav.play(pcm.clone(), 0, Repeats::Times(2)).unwrap();
+        let reader = hound::WavReader::open(&path).unwrap();
+        let spec = reader.spec();
+        let len = reader.len() as usize;
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(spec.channels, pcm.channels);
+        assert_eq!(spec.sample_rate, pcm.sample_rate);
+        assert_eq!(len, pcm.samples.len() * 2);
+    }
+
+    #[test]
+    fn aiff() {
+        play_sound(AIFF_BLORB, 3);
+    }
+
+    #[test]
+    fn ogg() {
+        play_sound(OGG_BLORB, 6);
+    }
+
+    #[test]
+    fn module() {
+        play_sound(MOD_BLORB, 7);
+    }
+
+    #[test]
+    fn corrupt_sounds_are_errors() {
+        let aiff = BlorbReader::try_from(AIFF_BLORB).unwrap();
+        assert!(matches!(decode_sound(&aiff, 4), Err(SoundError::Read(_))));
+        let ogg = BlorbReader::try_from(OGG_BLORB).unwrap();
+        assert!(matches!(decode_sound(&ogg, 5), Err(SoundError::Vorbis(_))));
+        assert!(matches!(
+            decode_sound(&ogg, 99),
+            Err(SoundError::NoSuchSound(99))
+        ));
+    }
+}
@@ -0,0 +1,263 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+
+use super::{Pcm, SoundError};
+use crate::file_reader::blorb_reader::Repeats;
+
+/// Somewhere decoded sounds can be played. Outputs play in the background, so `position` is
+/// polled to follow playback.
+pub trait AudioOutput {
+    /// Starts playing `pcm` from `start_frame`, replacing whatever was playing. Once the end is
+    /// reached, the sound starts again from the beginning until it has played `repeats` times.
+    fn play(
+        &mut self,
+        pcm: Arc<Pcm>,
+        start_frame: usize,
+        repeats: Repeats,
+    ) -> Result<(), SoundError>;
+    fn stop(&mut self);
+    /// The frame of the sound being played, or `None` if playback has stopped or played every
+    /// repeat.
+    fn position(&self) -> Option<usize>;
+    /// An error from playing in the background, which is only reported once.
+    fn take_error(&mut self) -> Option<SoundError> {
+        None
+    }
+    /// Whether there's no audio device behind the output, so nothing can be heard.
+    fn is_silent(&self) -> bool {
+        false
+    }
+}
+
+/// The audio device when the `cpal` feature is on and a device can be opened, otherwise an
+/// output that plays silently.
+pub fn default_output() -> Box<dyn AudioOutput> {
+    #[cfg(feature = "cpal")]
+    if let Ok(output) = cpal_output::CpalOutput::new() {
+        return Box::new(output);
+    }
+    Box::new(NullOutput::default())
+}
+
+/// Plays nothing, but keeps time as though it were, so that controls behave the same without an
+/// audio device.
+#[derive(Debug, Clone, Default)]
+pub struct NullOutput {
+    /// When playback started, from which frame, and of how many frames at what rate.
+    clock: Option<(Instant, usize, usize, u32)>,
+    repeats: Repeats,
+}
+
+impl AudioOutput for NullOutput {
+    fn play(
+        &mut self,
+        pcm: Arc<Pcm>,
+        start_frame: usize,
+        repeats: Repeats,
+    ) -> Result<(), SoundError> {
+        self.clock = Some((Instant::now(), start_frame, pcm.frames(), pcm.sample_rate));
+        self.repeats = repeats;
+        Ok(())
+    }
+
+    fn stop(&mut self) {
+        self.clock = None;
+    }
+
+    fn position(&self) -> Option<usize> {
+        let (started, start_frame, frames, rate) = self.clock?;
+        let position = start_frame + (started.elapsed().as_secs_f64() * rate as f64) as usize;
+        let finished = match self.repeats {
+            Repeats::Forever => frames == 0,
+            Repeats::Times(n) => position >= frames.saturating_mul(n.max(1) as usize),
+        };
+        (!finished).then(|| position % frames)
+    }
+
+    fn is_silent(&self) -> bool {
+        true
+    }
+}
+
+/// Writes each sound it's asked to play to a 16-bit WAV file, from the starting frame on, then
+/// keeps time like `NullOutput`. Repeats are written out in full, except that a sound which loops
+/// forever is written once.
+#[derive(Debug, Clone)]
+pub struct WavOutput {
+    pub path: PathBuf,
+    clock: NullOutput,
+}
+
+impl WavOutput {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        WavOutput {
+            path: path.into(),
+            clock: NullOutput::default(),
+        }
+    }
+}
+
+impl AudioOutput for WavOutput {
+    fn play(
+        &mut self,
+        pcm: Arc<Pcm>,
+        start_frame: usize,
+        repeats: Repeats,
+    ) -> Result<(), SoundError> {
+        let spec = hound::WavSpec {
+            channels: pcm.channels,
+            sample_rate: pcm.sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&self.path, spec)?;
+        let start = (start_frame * pcm.channels as usize).min(pcm.samples.len());
+        let passes = match repeats {
+            Repeats::Forever => 1,
+            Repeats::Times(n) => n.max(1) as usize,
+        };
+        let samples = pcm.samples[start..].iter().chain(
+            pcm.samples
+                .iter()
+                .cycle()
+                .take(pcm.samples.len() * (passes - 1)),
+        );
+        for &sample in samples {
+            writer.write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)?;
+        }
+        writer.finalize()?;
+        self.clock.play(pcm, start_frame, repeats)
+    }
+
+    fn stop(&mut self) {
+        self.clock.stop();
+    }
+
+    fn position(&self) -> Option<usize> {
+        self.clock.position()
+    }
+}
+
+#[cfg(feature = "cpal")]
+pub mod cpal_output {
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+    use super::AudioOutput;
+    use crate::file_reader::blorb_reader::Repeats;
+    use crate::sound::{Pcm, SoundError};
+
+    /// Plays through the system's default audio device, converting to its sample rate and
+    /// channel count as it goes.
+    pub struct CpalOutput {
+        device: cpal::Device,
+        config: cpal::StreamConfig,
+        stream: Option<cpal::Stream>,
+        /// The source frame being played, shared with the audio thread.
+        position: Arc<AtomicUsize>,
+        finished: Arc<AtomicBool>,
+        /// The last error the audio thread reported.
+        error: Arc<Mutex<Option<String>>>,
+    }
+
+    impl CpalOutput {
+        pub fn new() -> Result<Self, SoundError> {
+            let device = cpal::default_host()
+                .default_output_device()
+                .ok_or(SoundError::Output("no audio output device".to_string()))?;
+            let config = device
+                .default_output_config()
+                .map_err(|e| SoundError::Output(e.to_string()))?
+                .config();
+            Ok(CpalOutput {
+                device,
+                config,
+                stream: None,
+                position: Arc::new(AtomicUsize::new(0)),
+                finished: Arc::new(AtomicBool::new(true)),
+                error: Arc::new(Mutex::new(None)),
+            })
+        }
+    }
+
+    impl AudioOutput for CpalOutput {
+        fn play(
+            &mut self,
+            pcm: Arc<Pcm>,
+            start_frame: usize,
+            repeats: Repeats,
+        ) -> Result<(), SoundError> {
+            self.stop();
+            let out_channels = self.config.channels as usize;
+            let step = pcm.sample_rate as f64 / self.config.sample_rate.0 as f64;
+            let position = Arc::new(AtomicUsize::new(start_frame));
+            let finished = Arc::new(AtomicBool::new(false));
+            let (thread_position, thread_finished) = (position.clone(), finished.clone());
+            let thread_error = self.error.clone();
+            let mut source_frame = start_frame as f64;
+            let mut passes_left = match repeats {
+                Repeats::Forever => None,
+                Repeats::Times(n) => Some(n.max(1)),
+            };
+            let stream = self
+                .device
+                .build_output_stream(
+                    &self.config,
+                    move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                        let channels = pcm.channels.max(1) as usize;
+                        for frame in data.chunks_mut(out_channels) {
+                            if source_frame as usize >= pcm.frames()
+                                && pcm.frames() > 0
+                                && passes_left.is_none_or(|n| n > 1)
+                            {
+                                source_frame -= pcm.frames() as f64;
+                                passes_left = passes_left.map(|n| n - 1);
+                            }
+                            let index = source_frame as usize;
+                            if index >= pcm.frames() {
+                                frame.fill(0.0);
+                                thread_finished.store(true, Ordering::Relaxed);
+                                continue;
+                            }
+                            for (c, out) in frame.iter_mut().enumerate() {
+                                *out = pcm.samples[index * channels + c.min(channels - 1)];
+                            }
+                            source_frame += step;
+                        }
+                        thread_position.store(source_frame as usize, Ordering::Relaxed);
+                    },
+                    move |e| {
+                        if let Ok(mut error) = thread_error.lock() {
+                            *error = Some(e.to_string());
+                        }
+                    },
+                    None,
+                )
+                .map_err(|e| SoundError::Output(e.to_string()))?;
+            stream
+                .play()
+                .map_err(|e| SoundError::Output(e.to_string()))?;
+            self.stream = Some(stream);
+            self.position = position;
+            self.finished = finished;
+            Ok(())
+        }
+
+        fn stop(&mut self) {
+            self.stream = None;
+            self.finished.store(true, Ordering::Relaxed);
+        }
+
+        fn position(&self) -> Option<usize> {
+            (!self.finished.load(Ordering::Relaxed)).then(|| self.position.load(Ordering::Relaxed))
+        }
+
+        fn take_error(&mut self) -> Option<SoundError> {
+            let error = self.error.lock().ok()?.take()?;
+            Some(SoundError::Output(error))
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::HashSet;
+
+use super::Pcm;
+use crate::file_reader::tracker::{Note, TrackerModule, ROWS_PER_PATTERN};
+
+/// The rate modules are rendered at. Amiga samples are rarely above this, so it keeps long songs
+/// from using more memory than they need to.
+pub const RENDER_RATE: u32 = 22050;
+/// The clock Paula, the Amiga's sound chip, divides by a note's period to get its sample rate.
+const PAL_CLOCK: f64 = 7_093_789.2;
+/// Songs that never reach their end are cut off after this long.
+const MAX_SECONDS: usize = 300;
+const DEFAULT_SPEED: usize = 6;
+const DEFAULT_TEMPO: usize = 125;
+
+/// What each channel is playing.
+#[derive(Debug, Copy, Clone, Default)]
+struct Channel {
+    /// 1-based, with 0 meaning none yet.
+    sample: usize,
+    period: u16,
+    volume: u8,
+    /// Position in the sample, in bytes.
+    position: f64,
+    playing: bool,
+    /// Change in volume on every tick but the first, from effect `A`.
+    volume_slide: i8,
+}
+
+/// Plays a module through to the end of its song, or until it loops back to a row it has
+/// already played, as stereo PCM. Channels are panned hard left and right the way an Amiga
+/// does: 0 and 3 on the left, 1 and 2 on the right.
+///
+/// The effects that change which row plays next, the speed, the volume and the sample offset
+/// are followed. Pitch effects such as portamento, vibrato and arpeggio are ignored.
+pub fn render(module: &TrackerModule) -> Pcm {
+    let mut channels = vec![Channel::default(); module.channels];
+    let mut samples = Vec::new();
+    let mut speed = DEFAULT_SPEED;
+    let mut tempo = DEFAULT_TEMPO;
+    let mut visited = HashSet::new();
+    let (mut order, mut row) = (0, 0);
+    let max_samples = RENDER_RATE as usize * MAX_SECONDS * 2;
+
+    while order < module.song_length && samples.len() < max_samples {
+        if !visited.insert((order, row)) {
+            break;
+        }
+        let pattern = &module.patterns[module.order[order] as usize];
+        let mut jump = None;
+        for (channel, note) in channels.iter_mut().zip(&pattern.rows[row]) {
+            start_note(module, channel, note);
+            channel.volume_slide = 0;
+            match note.effect {
+                0x9 if note.parameter > 0 => channel.position = note.parameter as f64 * 256.0,
+                0xA => {
+                    channel.volume_slide = if note.parameter >> 4 > 0 {
+                        (note.parameter >> 4) as i8
+                    } else {
+                        -((note.parameter & 0x0F) as i8)
+                    }
+                }
+                0xB => jump = Some((note.parameter as usize, 0)),
+                0xC => channel.volume = note.parameter.min(64),
+                0xD => {
+                    let target = ((note.parameter >> 4) * 10 + (note.parameter & 0x0F)) as usize;
+                    let next_order = jump.map_or(order + 1, |(o, _)| o);
+                    jump = Some((next_order, target.min(ROWS_PER_PATTERN - 1)));
+                }
+                0xF if note.parameter > 0 && note.parameter < 32 => speed = note.parameter as usize,
+                0xF if note.parameter >= 32 => tempo = note.parameter as usize,
+                _ => {}
+            }
+        }
+
+        // A tick lasts 2.5 / tempo seconds.
+        let tick_len = RENDER_RATE as usize * 5 / (tempo * 2);
+        for tick in 0..speed {
+            if tick > 0 {
+                channels.iter_mut().for_each(|c| {
+                    c.volume = (c.volume as i8 + c.volume_slide).clamp(0, 64) as u8;
+                });
+            }
+            mix(module, &mut channels, tick_len, &mut samples);
+        }
+
+        (order, row) = match jump {
+            Some(next) => next,
+            None if row + 1 == ROWS_PER_PATTERN => (order + 1, 0),
+            None => (order, row + 1),
+        };
+    }
+
+    Pcm {
+        sample_rate: RENDER_RATE,
+        channels: 2,
+        samples,
+    }
+}
+
+/// Sets up a channel for a row's note: a sample number changes the instrument and resets the
+/// volume, and a period starts the note.
+fn start_note(module: &TrackerModule, channel: &mut Channel, note: &Note) {
+    let sample = note.sample as usize;
+    if sample > 0 && sample <= module.samples.len() {
+        channel.sample = sample;
+        channel.volume = module.samples[sample - 1].volume;
+    }
+    // Tone portamento slides to the period rather than starting a new note.
+    if note.period > 0 && note.effect != 0x3 {
+        channel.period = note.period;
+        channel.position = 0.0;
+        channel.playing = channel.sample > 0;
+    }
+}
+
+/// Adds `frames` stereo frames of every channel to `out`.
+fn mix(module: &TrackerModule, channels: &mut [Channel], frames: usize, out: &mut Vec<f32>) {
+    let start = out.len();
+    out.resize(start + frames * 2, 0.0);
+    // Each side carries half the channels, so scaling by that keeps the mix from clipping.
+    let scale = 2.0 / channels.len().max(2) as f32;
+    for (i, channel) in channels.iter_mut().enumerate() {
+        if !channel.playing || channel.period == 0 {
+            continue;
+        }
+        let sample = &module.samples[channel.sample - 1];
+        let finetune = 2f64.powf(sample.finetune as f64 / 96.0);
+        let step = PAL_CLOCK / (channel.period as f64 * 2.0) * finetune / RENDER_RATE as f64;
+        let volume = channel.volume as f32 / 64.0 * scale;
+        let side = if i % 4 == 0 || i % 4 == 3 { 0 } else { 1 };
+        for frame in out[start..].chunks_exact_mut(2) {
+            while sample.loops()
+                && channel.position as usize >= sample.loop_start + sample.loop_length
+            {
+                channel.position -= sample.loop_length as f64;
+            }
+            let position = channel.position as usize;
+            let Some(&value) = sample.data.get(position) else {
+                channel.playing = false;
+                break;
+            };
+            frame[side] += value as i8 as f32 / 128.0 * volume;
+            channel.position += step;
+        }
+    }
+}
@@ -1,8 +1,14 @@
+use std::fmt::{Display, Formatter};
+
+use crate::file_reader::decoding_table::DecodingTable;
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub enum StringTypes {
     CStyle = 0xE0,
     Compressed,
     CStyleUnicode,
+    /// Z-machine text, which has no type byte.
+    Zscii,
 }
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub enum ParsingErrors {
@@ -24,17 +30,58 @@ impl TryFrom<u8> for StringTypes {
 }
 
 impl StringTypes {
-    pub fn parse(&self, data: &[u8]) -> String {
+    /// Decodes the string whose data starts immediately after its type byte. Compressed strings
+    /// need the game's decoding table and come back empty without one.
+    pub fn parse(&self, data: &[u8], decoding_table: Option<&DecodingTable>) -> String {
+        self.try_parse(data, decoding_table).unwrap_or_default()
+    }
+
+    /// As `parse`, but returns `None` if the data is not a well-formed string of this type, such
+    /// as one that runs off the end of memory before its terminator. Z-machine text needs the
+    /// story's alphabets and abbreviations, so it always comes back `None`; use `ZsciiDecoder`.
+    pub fn try_parse(&self, data: &[u8], decoding_table: Option<&DecodingTable>) -> Option<String> {
+        match self {
+            StringTypes::CStyle => {
+                let end = data.iter().position(|&b| b == 0)?;
+                String::from_utf8(data[..end].to_vec()).ok()
+            }
+            StringTypes::Compressed => decoding_table?.decode(data).ok(),
+            StringTypes::CStyleUnicode => {
+                // Three bytes of padding keep the code points that follow word aligned.
+                if data.len() < 3 || data[..3] != [0, 0, 0] {
+                    return None;
+                }
+                let mut ret = String::new();
+                for word in data[3..].chunks_exact(4) {
+                    match u32::from_be_bytes(word.try_into().unwrap()) {
+                        0 => return Some(ret),
+                        c => push_code_point(&mut ret, c),
+                    }
+                }
+                // Never found the terminating zero word.
+                None
+            }
+            StringTypes::Zscii => None,
+        }
+    }
+}
+
+impl Display for StringTypes {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            StringTypes::CStyle => String::from_utf8(
-                data.split(|&b| b == 0)
-                    .next()
-                    .expect("Never found '0' byte while parsing C-Style string.")
-                    .to_vec(),
-            )
-            .unwrap_or("".to_string()),
-            StringTypes::Compressed => "".to_string(),
-            StringTypes::CStyleUnicode => "".to_string(),
+            StringTypes::CStyle => f.write_str("C-style"),
+            StringTypes::Compressed => f.write_str("Compressed"),
+            StringTypes::CStyleUnicode => f.write_str("Unicode"),
+            StringTypes::Zscii => f.write_str("ZSCII"),
         }
     }
 }
+
+/// Appends a Glulx unicode code point, writing surrogates and values past U+10FFFF as a visible
+/// `[invalid U+XXXX]` marker rather than dropping them.
+pub(crate) fn push_code_point(out: &mut String, code_point: u32) {
+    match char::from_u32(code_point) {
+        Some(c) => out.push(c),
+        None => out.push_str(&format!("[invalid U+{code_point:04X}]")),
+    }
+}